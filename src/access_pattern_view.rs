@@ -0,0 +1,152 @@
+//! A visual rendering of an access pattern: a grid with one cell for each
+//! index into the iteration dimensions (the dimensions before the access
+//! axis), each of which can be expanded to show the item (the subview formed
+//! by the dimensions from the access axis onwards).
+
+use crate::result_format::{format_access_shape, format_dims};
+use ndarray::{ArrayD, Axis};
+use std::rc::Rc;
+use yew::{html, Component, ComponentLink, Html, Properties, ShouldRender};
+
+/// The most cells we'll draw. Beyond this, the browser grinds to a halt.
+const MAX_CELLS: usize = 256;
+
+/// Lays the iteration dimensions out as a 2D grid: the last dimension runs
+/// along the columns, and all of the others along the rows. Returns the
+/// number of rows and columns.
+pub fn grid_shape(iteration_shape: &[usize]) -> (usize, usize) {
+    match iteration_shape.split_last() {
+        Some((&columns, rest)) => (rest.iter().product(), columns),
+        None => (1, 1),
+    }
+}
+
+/// Converts a position in the grid laid out by [`grid_shape`] back into an
+/// index into the iteration dimensions.
+pub fn grid_index(iteration_shape: &[usize], row: usize, column: usize) -> Vec<usize> {
+    let mut index = vec![0; iteration_shape.len()];
+    if let Some((last, rest)) = index.split_last_mut() {
+        *last = column;
+        let mut row = row;
+        for (i, &dim) in rest
+            .iter_mut()
+            .zip(&iteration_shape[..iteration_shape.len() - 1])
+            .rev()
+        {
+            *i = row % dim;
+            row /= dim;
+        }
+    }
+    index
+}
+
+#[derive(Properties, Clone)]
+pub struct AccessPatternViewProps {
+    pub tensor: Rc<ArrayD<f64>>,
+    pub access_axis: usize,
+}
+
+pub struct AccessPatternView {
+    props: AccessPatternViewProps,
+}
+
+impl AccessPatternView {
+    fn view_cell(&self, index: &[usize]) -> Html {
+        let mut item = self.props.tensor.view();
+        for &i in index {
+            item = item.index_axis_move(Axis(0), i);
+        }
+
+        if item.ndim() == 0 {
+            // Scalar items are shown directly.
+            html! {
+                <td style={"border: 1px solid #888; padding: 2px"}
+                    title={format!("({})", format_dims(index))}>
+                    {format!("{:.2}", item)}
+                </td>
+            }
+        } else {
+            html! {
+                <td style={"border: 1px solid #888; padding: 2px; vertical-align: top"}>
+                    <details>
+                        <summary>{format!("({})", format_dims(index))}</summary>
+                        <pre style={"background-color: #eef"}>{format!("{:.2}", item)}</pre>
+                    </details>
+                </td>
+            }
+        }
+    }
+}
+
+impl Component for AccessPatternView {
+    type Message = ();
+    type Properties = AccessPatternViewProps;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        unreachable!()
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let shape = self.props.tensor.shape();
+        let iteration_shape = &shape[..self.props.access_axis];
+        let (rows, columns) = grid_shape(iteration_shape);
+        let shown_columns = columns.min(MAX_CELLS);
+        let shown_rows = rows.min(MAX_CELLS / shown_columns.max(1));
+
+        html! {
+            <div>
+                <p>
+                    {format!(
+                        "access pattern {}: a grid of shape ({}), where each cell holds an item of shape ({})",
+                        format_access_shape(shape, self.props.access_axis),
+                        format_dims(iteration_shape),
+                        format_dims(&shape[self.props.access_axis..]),
+                    )}
+                </p>
+                <table style={"border-collapse: collapse"}>
+                    { for (0..shown_rows).map(|row| html! {
+                        <tr>
+                            { for (0..shown_columns).map(|column| {
+                                self.view_cell(&grid_index(iteration_shape, row, column))
+                            }) }
+                        </tr>
+                    }) }
+                </table>
+                {
+                    if shown_rows < rows || shown_columns < columns {
+                        html! {
+                            <p>{format!("(showing {} of {} cells)", shown_rows * shown_columns, rows * columns)}</p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid() {
+        assert_eq!(grid_shape(&[]), (1, 1));
+        assert_eq!(grid_shape(&[2]), (1, 2));
+        assert_eq!(grid_shape(&[2, 3, 4]), (6, 4));
+
+        assert_eq!(grid_index(&[], 0, 0), Vec::<usize>::new());
+        assert_eq!(grid_index(&[2], 0, 1), vec![1]);
+        assert_eq!(grid_index(&[2, 3, 4], 4, 2), vec![1, 1, 2]);
+    }
+}
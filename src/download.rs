@@ -0,0 +1,33 @@
+//! Downloading of files generated in the browser.
+
+use gloo_timers::callback::Timeout;
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+/// Makes the browser download `bytes` as a file called `file_name`.
+pub fn download(file_name: &str, bytes: &[u8]) {
+    let mut options = BlobPropertyBag::new();
+    options.type_("application/octet-stream");
+    let blob = Blob::new_with_u8_array_sequence_and_options(
+        &Array::of1(&Uint8Array::from(bytes)),
+        &options,
+    )
+    .unwrap();
+    let url = Url::create_object_url_with_blob(&blob).unwrap();
+
+    // Downloads are started by clicking on a link to the file. Some browsers
+    // (Firefox) ignore clicks on links which aren't in the document.
+    let document = web_sys::window().unwrap().document().unwrap();
+    let body = document.body().unwrap();
+    let anchor: HtmlAnchorElement = document.create_element("a").unwrap().unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    body.append_child(&anchor).unwrap();
+    anchor.click();
+    body.remove_child(&anchor).unwrap();
+
+    // The download may not have started reading the blob by the time the click
+    // returns, so only let go of it once it has had a chance to.
+    Timeout::new(10_000, move || Url::revoke_object_url(&url).unwrap()).forget();
+}
@@ -0,0 +1,75 @@
+//! Helpers for driving the Monaco editor.
+
+use crate::js;
+use crate::spans::{line_and_column, Span};
+use monaco::api::CodeEditor;
+use monaco::sys::editor::IStandaloneCodeEditor;
+use wasm_bindgen::{closure::Closure, JsValue};
+
+/// Converts a span of `source` into a `monaco.IRange`.
+pub fn range(source: &str, span: Span) -> JsValue {
+    let (start_line, start_column) = line_and_column(source, span.start);
+    let (end_line, end_column) = line_and_column(source, span.end);
+    js::object(&[
+        ("startLineNumber", start_line.into()),
+        ("startColumn", start_column.into()),
+        ("endLineNumber", end_line.into()),
+        ("endColumn", end_column.into()),
+    ])
+    .into()
+}
+
+/// Selects `span` of `source` in the editor, scrolling it into view if needed.
+/// `source` should be the editor's current text. The cursor is left at the
+/// start of the span, so that the node starting there stays under the cursor.
+pub fn select(editor: &CodeEditor, source: &str, span: Span) {
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    let (start_line, start_column) = line_and_column(source, span.start);
+    let (end_line, end_column) = line_and_column(source, span.end);
+    let selection = js::object(&[
+        ("selectionStartLineNumber", end_line.into()),
+        ("selectionStartColumn", end_column.into()),
+        ("positionLineNumber", start_line.into()),
+        ("positionColumn", start_column.into()),
+    ]);
+    js::call_method(editor, "setSelection", &[selection.into()]);
+    js::call_method(
+        editor,
+        "revealRangeInCenterIfOutsideViewport",
+        &[range(source, span)],
+    );
+}
+
+/// Calls `callback` with the character offset of the cursor whenever the
+/// cursor moves. The listener lives as long as the editor.
+pub fn on_cursor_moved(editor: &CodeEditor, callback: yew::Callback<usize>) {
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    let model = js::call_method(editor, "getModel", &[]);
+    let listener = Closure::wrap(Box::new(move |event: JsValue| {
+        let position = js::get(&event, "position");
+        let offset = js::call_method(&model, "getOffsetAt", &[position])
+            .as_f64()
+            .unwrap_or_default();
+        callback.emit(offset as usize);
+    }) as Box<dyn Fn(JsValue)>);
+    js::call_method(
+        editor,
+        "onDidChangeCursorPosition",
+        &[listener.as_ref().clone()],
+    );
+    listener.forget();
+}
+
+/// Calls `callback` whenever the text in the editor changes. The listener
+/// lives as long as the editor.
+pub fn on_content_changed(editor: &CodeEditor, callback: yew::Callback<()>) {
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    let listener =
+        Closure::wrap(Box::new(move |_event: JsValue| callback.emit(())) as Box<dyn Fn(JsValue)>);
+    js::call_method(
+        editor,
+        "onDidChangeModelContent",
+        &[listener.as_ref().clone()],
+    );
+    listener.forget();
+}
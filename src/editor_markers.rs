@@ -0,0 +1,35 @@
+//! Squiggly underlines in the editor marking the source of an evaluation
+//! error.
+
+use crate::editor;
+use crate::evaluation::EvaluationError;
+use crate::js;
+use js_sys::Array;
+use monaco::api::CodeEditor;
+use wasm_bindgen::JsCast;
+
+/// The owner under which we register our markers with Monaco.
+const MARKER_OWNER: &str = "glenside";
+/// The value of `monaco.MarkerSeverity.Error`.
+const MARKER_SEVERITY_ERROR: u32 = 8;
+
+/// Replaces the editor's error markers with one for `error`, or clears them if
+/// `error` is `None`. `source` should be the text `error` came from.
+pub fn set_error_markers(editor: &CodeEditor, source: &str, error: Option<&EvaluationError>) {
+    let markers = Array::new();
+
+    if let Some(error) = error {
+        let marker = editor::range(source, error.span(source));
+        js::set(&marker, "message", error.to_string().into());
+        js::set(&marker, "severity", MARKER_SEVERITY_ERROR.into());
+        markers.push(&marker);
+    }
+
+    if let Some(model) = editor.get_model() {
+        monaco::sys::editor::set_model_markers(
+            model.as_ref(),
+            MARKER_OWNER,
+            markers.unchecked_ref(),
+        );
+    }
+}
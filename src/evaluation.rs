@@ -0,0 +1,412 @@
+//! Fallible evaluation of Glenside programs.
+//!
+//! Glenside's [`interpret_from_str`] panics on any malformed input. The
+//! functions here check programs up front (parsing, unbound variables and,
+//! with [`shape_check`], shapes), turning failures into an
+//! [`EvaluationError`] which can be shown to the user. The module docs of
+//! [`shape_check`] explain why the checks, rather than catching panics, are
+//! what keep the app alive.
+//!
+//! [`interpret_from_str`]: glenside::language::interpreter::interpret_from_str
+
+use crate::shape_check;
+use crate::spans::{self, Span};
+use egg::RecExpr;
+use glenside::language::interpreter::{interpret, Access, Environment, Value};
+use glenside::language::Language;
+use ndarray::ArrayD;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationError {
+    /// The program could not be parsed. `position` is the character offset
+    /// into the source at which the problem was found, if we could find it.
+    Parse {
+        message: String,
+        position: Option<usize>,
+    },
+    /// The program refers to a tensor which isn't in the environment. `node`
+    /// is the id of the offending symbol.
+    UnboundVariable { name: String, node: usize },
+    /// The shapes of the arguments to some operator don't line up. `node` is
+    /// the id of the innermost node at fault, as found by the shape check. It's
+    /// `None` if the check missed the problem and the interpreter failed
+    /// instead.
+    Shape {
+        message: String,
+        node: Option<usize>,
+    },
+    /// The program uses an operator which the shape check doesn't cover.
+    /// `node` is the id of the first node using it.
+    UnsupportedOperator { operator: String, node: usize },
+}
+
+impl EvaluationError {
+    /// The span of `source` responsible for this error. Falls back to the
+    /// whole program if the error can't be pinned down any further.
+    pub fn span(&self, source: &str) -> Span {
+        let node = match self {
+            EvaluationError::Parse {
+                position: Some(position),
+                ..
+            } => {
+                return Span {
+                    start: *position,
+                    end: position + 1,
+                }
+            }
+            EvaluationError::Parse { position: None, .. } => None,
+            EvaluationError::UnboundVariable { node, .. } => Some(*node),
+            EvaluationError::Shape { node, .. } => *node,
+            EvaluationError::UnsupportedOperator { node, .. } => Some(*node),
+        };
+
+        node.and_then(|node| spans::node_spans(source).and_then(|spans| spans.get(node).copied()))
+            .unwrap_or(Span {
+                start: 0,
+                end: source.chars().count(),
+            })
+    }
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluationError::Parse {
+                message,
+                position: Some(position),
+            } => write!(f, "parse error at character {}:\n{}", position, message),
+            EvaluationError::Parse {
+                message,
+                position: None,
+            } => write!(f, "parse error:\n{}", message),
+            EvaluationError::UnboundVariable { name, .. } => write!(
+                f,
+                "unbound variable:\n\"{}\" is not in the environment",
+                name
+            ),
+            EvaluationError::Shape { message, .. } => write!(f, "shape error:\n{}", message),
+            EvaluationError::UnsupportedOperator { operator, .. } => write!(
+                f,
+                "unsupported operator:\n\"{}\" isn't supported yet, as its shapes \
+                 can't be checked",
+                operator
+            ),
+        }
+    }
+}
+
+/// Finds the position of a structural problem in an s-expression: an
+/// unmatched closing paren, an unclosed opening paren, or text following the
+/// end of the first complete expression. Returns `None` if the parens look
+/// fine.
+fn find_structural_error(source: &str) -> Option<(String, usize)> {
+    // Positions of the currently unclosed open parens.
+    let mut open = Vec::new();
+    // Whether we've seen a complete top-level expression.
+    let mut complete = false;
+    let mut in_atom = false;
+
+    for (i, c) in source.chars().enumerate() {
+        if complete && c == ')' {
+            return Some(("unmatched \")\"".to_string(), i));
+        }
+        if complete && !c.is_whitespace() {
+            return Some(("unexpected text after end of expression".to_string(), i));
+        }
+        match c {
+            '(' => {
+                in_atom = false;
+                open.push(i);
+            }
+            ')' => {
+                in_atom = false;
+                if open.pop().is_none() {
+                    return Some(("unmatched \")\"".to_string(), i));
+                }
+                complete = open.is_empty();
+            }
+            c if c.is_whitespace() => {
+                if in_atom && open.is_empty() {
+                    complete = true;
+                }
+                in_atom = false;
+            }
+            _ => in_atom = true,
+        }
+    }
+
+    if let Some(&position) = open.last() {
+        return Some(("unclosed \"(\"".to_string(), position));
+    }
+
+    None
+}
+
+/// Parses a Glenside program.
+pub fn parse(source: &str) -> Result<RecExpr<Language>, EvaluationError> {
+    if source.trim().is_empty() {
+        return Err(EvaluationError::Parse {
+            message: "empty program".to_string(),
+            position: Some(0),
+        });
+    }
+
+    if let Some((message, position)) = find_structural_error(source) {
+        return Err(EvaluationError::Parse {
+            message,
+            position: Some(position),
+        });
+    }
+
+    RecExpr::from_str(source).map_err(|message| EvaluationError::Parse {
+        message,
+        position: None,
+    })
+}
+
+/// Checks that every variable in `expr` is bound, according to `is_bound`.
+pub fn check_bound(
+    expr: &RecExpr<Language>,
+    is_bound: impl Fn(&str) -> bool,
+) -> Result<(), EvaluationError> {
+    match expr
+        .as_ref()
+        .iter()
+        .enumerate()
+        .find_map(|(id, node)| match node {
+            Language::Symbol(name) if !is_bound(name) => Some((id, name)),
+            _ => None,
+        }) {
+        Some((node, name)) => Err(EvaluationError::UnboundVariable {
+            name: name.clone(),
+            node,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Turns the payload of a caught panic into a message.
+pub fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "the interpreter panicked".to_string()
+    }
+}
+
+/// Parses a Glenside program and checks it against `env`, as far as that's
+/// possible without interpreting it. Programs whose shapes can't all be
+/// checked are rejected, as the interpreter might panic on them.
+fn parse_and_check(
+    source: &str,
+    env: &Environment<f64>,
+) -> Result<RecExpr<Language>, EvaluationError> {
+    let expr = parse(source)?;
+    check_bound(&expr, |name| env.contains_key(name))?;
+    let shapes = env
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.shape().to_vec()))
+        .collect::<HashMap<_, _>>();
+    let checked = shape_check::check(&expr, &shapes)?;
+    shape_check::check_supported(&expr, &checked)?;
+    Ok(expr)
+}
+
+/// Parses and interprets a Glenside program, reporting failures as an
+/// [`EvaluationError`] rather than panicking.
+pub fn evaluate(source: &str, env: &Environment<f64>) -> Result<Value<f64>, EvaluationError> {
+    let expr = parse_and_check(source, env)?;
+
+    catch_unwind(AssertUnwindSafe(|| {
+        interpret(&expr, expr.as_ref().len() - 1, env)
+    }))
+    .map_err(|payload| EvaluationError::Shape {
+        message: panic_message(payload),
+        // Shape errors which can be pinned to a node are caught by the shape
+        // check, before we get here.
+        node: None,
+    })
+}
+
+/// The value of every node of a program, as produced by [`trace`].
+pub struct Trace {
+    pub expr: RecExpr<Language>,
+    /// The span of each node in the source, indexed by node id.
+    pub spans: Vec<Span>,
+    /// The value of each node, indexed by node id. Node ids are assigned in
+    /// post-order, so this is also the order in which the interpreter
+    /// finishes evaluating each node.
+    pub values: Vec<Value<f64>>,
+    /// The tensor of each node whose value is a tensor or an access pattern,
+    /// indexed by node id. These are shared, so that showing them doesn't
+    /// mean copying them.
+    pub tensors: Vec<Option<Rc<ArrayD<f64>>>>,
+}
+
+/// Parses and interprets a Glenside program, recording the value of every
+/// node along the way.
+///
+/// Each node is interpreted separately, which means re-interpreting its
+/// children. That's wasteful, but Glenside's interpreter doesn't expose
+/// intermediate values, and the programs in this demo are small.
+pub fn trace(source: &str, env: &Environment<f64>) -> Result<Trace, EvaluationError> {
+    let expr = parse_and_check(source, env)?;
+
+    let values = (0..expr.as_ref().len())
+        .map(|id| {
+            catch_unwind(AssertUnwindSafe(|| interpret(&expr, id, env))).map_err(|payload| {
+                EvaluationError::Shape {
+                    message: panic_message(payload),
+                    node: Some(id),
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let tensors = values
+        .iter()
+        .map(|value| match value {
+            Value::Tensor(t) | Value::Access(Access { tensor: t, .. }) => Some(Rc::new(t.clone())),
+            _ => None,
+        })
+        .collect();
+
+    Ok(Trace {
+        // We only get here if the program parsed, in which case it is a
+        // well-formed s-expression.
+        spans: spans::node_spans(source).unwrap(),
+        expr,
+        values,
+        tensors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{ArrayD, IxDyn};
+    use std::collections::HashMap;
+
+    fn env() -> Environment<'static, f64> {
+        let mut env = HashMap::new();
+        env.insert("t", ArrayD::zeros(IxDyn(&[2, 3])));
+        env
+    }
+
+    fn error(source: &str) -> EvaluationError {
+        match evaluate(source, &env()) {
+            Err(e) => e,
+            Ok(_) => panic!("expected {} to fail", source),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            error(""),
+            EvaluationError::Parse {
+                message: "empty program".to_string(),
+                position: Some(0)
+            }
+        );
+        assert_eq!(
+            error("(access-tensor t))"),
+            EvaluationError::Parse {
+                message: "unmatched \")\"".to_string(),
+                position: Some(17)
+            }
+        );
+        assert_eq!(
+            error("(access (access-tensor t) 1"),
+            EvaluationError::Parse {
+                message: "unclosed \"(\"".to_string(),
+                position: Some(0)
+            }
+        );
+        assert_eq!(
+            error("(access-tensor t) t"),
+            EvaluationError::Parse {
+                message: "unexpected text after end of expression".to_string(),
+                position: Some(18)
+            }
+        );
+    }
+
+    #[test]
+    fn unbound_variable() {
+        assert_eq!(
+            error("(access-tensor a)"),
+            EvaluationError::UnboundVariable {
+                name: "a".to_string(),
+                node: 0
+            }
+        );
+    }
+
+    #[test]
+    fn shape_error() {
+        match error(
+            "(access-cartesian-product (access (access-tensor t) 1) (access (access-tensor t) 0))",
+        ) {
+            EvaluationError::Shape { node, .. } => assert_eq!(node, Some(8)),
+            other => panic!("expected a shape error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unsupported_operator() {
+        assert_eq!(
+            error("(access-insert-axis (access-tensor t) 9)"),
+            EvaluationError::UnsupportedOperator {
+                operator: "access-insert-axis".to_string(),
+                node: 3
+            }
+        );
+    }
+
+    #[test]
+    fn error_spans() {
+        // The pad axis is out of range.
+        let source = "(access (access-pad (access-tensor t) zero-padding 5 1 1) 0)";
+        assert_eq!(error(source).span(source), Span { start: 8, end: 57 });
+        assert_eq!(
+            error("(access-tensor a)").span("(access-tensor a)"),
+            Span { start: 15, end: 16 }
+        );
+        assert_eq!(
+            error("(access-tensor t))").span("(access-tensor t))"),
+            Span { start: 17, end: 18 }
+        );
+    }
+
+    #[test]
+    fn trace_values() {
+        let trace = trace("(access (access-tensor t) 1)", &env()).unwrap();
+        assert_eq!(trace.values.len(), 4);
+        assert_eq!(trace.spans[1], Span { start: 8, end: 25 });
+        match &trace.values[1] {
+            Value::Access(a) => assert_eq!(a.access_axis, 0),
+            _ => panic!(),
+        }
+        match &trace.values[3] {
+            Value::Access(a) => assert_eq!(a.access_axis, 1),
+            _ => panic!(),
+        }
+        assert_eq!(trace.tensors[2], None);
+        assert_eq!(trace.tensors[3].as_ref().unwrap().shape(), &[2, 3]);
+    }
+
+    #[test]
+    fn success() {
+        match evaluate("t", &env()) {
+            Ok(Value::Tensor(t)) => assert_eq!(t, ArrayD::<f64>::zeros(IxDyn(&[2, 3]))),
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Registration of Glenside as a language in Monaco, giving the editor syntax
+//! highlighting, bracket matching, completions, and hover cards showing
+//! operator documentation and inferred shapes.
+
+use crate::editor;
+use crate::evaluation;
+use crate::js;
+use crate::operators::{self, COMPUTE_TYPES, OPERATORS, PAD_TYPES};
+use crate::shape_check;
+use crate::shape_inference;
+use crate::spans::{self, Span};
+use egg::Language as _;
+use js_sys::{Array, RegExp};
+use monaco::sys::languages;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// The id under which Glenside is registered with Monaco.
+pub const LANGUAGE_ID: &str = "glenside";
+
+/// Values of `monaco.languages.CompletionItemKind`.
+const COMPLETION_ITEM_KIND_FUNCTION: u32 = 1;
+const COMPLETION_ITEM_KIND_VARIABLE: u32 = 4;
+const COMPLETION_ITEM_KIND_ENUM_MEMBER: u32 = 16;
+/// The value of
+/// `monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet`.
+const INSERT_AS_SNIPPET: u32 = 4;
+
+thread_local! {
+    /// The shapes of the tensors in the current environment, as last given to
+    /// [`set_environment_shapes`]. Read by the completion and hover providers.
+    static ENVIRONMENT_SHAPES: RefCell<HashMap<String, Vec<usize>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Builds the Monarch tokenizer definition for Glenside. `variables` are the
+/// names in the current environment, which get highlighted as variables.
+fn monarch_definition<'a>(variables: impl IntoIterator<Item = &'a str>) -> JsValue {
+    let rule = |regex: &str, action: JsValue| -> JsValue {
+        Array::of2(&RegExp::new(regex, ""), &action).into()
+    };
+
+    js::object(&[
+        (
+            "operators",
+            js::string_array(OPERATORS.iter().map(|operator| operator.name)).into(),
+        ),
+        (
+            "computeTypes",
+            js::string_array(COMPUTE_TYPES.iter().copied()).into(),
+        ),
+        (
+            "padTypes",
+            js::string_array(PAD_TYPES.iter().copied()).into(),
+        ),
+        ("variables", js::string_array(variables).into()),
+        (
+            "brackets",
+            Array::of1(&js::object(&[
+                ("open", "(".into()),
+                ("close", ")".into()),
+                ("token", "delimiter.parenthesis".into()),
+            ]))
+            .into(),
+        ),
+        (
+            "tokenizer",
+            js::object(&[(
+                "root",
+                Array::of4(
+                    &rule(r"[()]", "@brackets".into()),
+                    &rule(r"\d+(?![^\s()])", "number".into()),
+                    &rule(
+                        r"[^\s()]+",
+                        js::object(&[(
+                            "cases",
+                            js::object(&[
+                                ("@operators", "keyword".into()),
+                                ("@computeTypes", "type".into()),
+                                ("@padTypes", "type.identifier".into()),
+                                ("@variables", "variable".into()),
+                                ("@default", "identifier".into()),
+                            ])
+                            .into(),
+                        )])
+                        .into(),
+                    ),
+                    &rule(r"\s+", "white".into()),
+                )
+                .into(),
+            )])
+            .into(),
+        ),
+    ])
+    .into()
+}
+
+/// Registers the Glenside language with Monaco. Should be called once, before
+/// any editor using [`LANGUAGE_ID`] is created.
+pub fn register() {
+    languages::register(js::object(&[("id", LANGUAGE_ID.into())]).unchecked_ref());
+
+    let brackets = Array::of1(&Array::of2(&"(".into(), &")".into()));
+    let pair = js::object(&[("open", "(".into()), ("close", ")".into())]);
+    languages::set_language_configuration(
+        LANGUAGE_ID,
+        js::object(&[
+            ("brackets", brackets.into()),
+            ("autoClosingPairs", Array::of1(&pair).into()),
+            ("surroundingPairs", Array::of1(&pair).into()),
+            // Glenside's operators contain hyphens, which Monaco doesn't
+            // include in words by default.
+            ("wordPattern", RegExp::new(r"[^\s()]+", "g").into()),
+        ])
+        .unchecked_ref(),
+    );
+
+    set_environment_shapes(HashMap::new());
+
+    let provide_completion_items = Closure::wrap(
+        Box::new(provide_completion_items) as Box<dyn Fn(JsValue, JsValue) -> JsValue>
+    );
+    languages::register_completion_item_provider(
+        LANGUAGE_ID,
+        js::object(&[
+            ("triggerCharacters", js::string_array(vec!["("]).into()),
+            (
+                "provideCompletionItems",
+                provide_completion_items.as_ref().clone(),
+            ),
+        ])
+        .unchecked_ref(),
+    );
+    // The provider lives as long as the page.
+    provide_completion_items.forget();
+
+    let provide_hover =
+        Closure::wrap(Box::new(provide_hover) as Box<dyn Fn(JsValue, JsValue) -> JsValue>);
+    languages::register_hover_provider(
+        LANGUAGE_ID,
+        js::object(&[("provideHover", provide_hover.as_ref().clone())]).unchecked_ref(),
+    );
+    provide_hover.forget();
+}
+
+/// Updates the names highlighted and completed as variables, and the shapes
+/// used for shape inference in hover cards. Should be called whenever the
+/// environment changes.
+pub fn set_environment_shapes(shapes: HashMap<String, Vec<usize>>) {
+    languages::set_monarch_tokens_provider(
+        LANGUAGE_ID,
+        monarch_definition(shapes.keys().map(String::as_str)).unchecked_ref(),
+    );
+    ENVIRONMENT_SHAPES.with(|s| *s.borrow_mut() = shapes);
+}
+
+/// Wraps a markdown string as a `monaco.IMarkdownString`.
+fn markdown(value: &str) -> JsValue {
+    js::object(&[("value", value.into())]).into()
+}
+
+/// Implements `CompletionItemProvider.provideCompletionItems`, suggesting
+/// operators (with snippets for their arguments), compute types, pad types
+/// and the names in the environment.
+fn provide_completion_items(model: JsValue, position: JsValue) -> JsValue {
+    let word = js::call_method(
+        &model,
+        "getWordUntilPosition",
+        std::slice::from_ref(&position),
+    );
+    let line = js::get(&position, "lineNumber");
+    let range: JsValue = js::object(&[
+        ("startLineNumber", line.clone()),
+        ("endLineNumber", line),
+        ("startColumn", js::get(&word, "startColumn")),
+        ("endColumn", js::get(&word, "endColumn")),
+    ])
+    .into();
+
+    let suggestion = |label: &str, kind: u32, detail: &str| {
+        js::object(&[
+            ("label", label.into()),
+            ("kind", kind.into()),
+            ("insertText", label.into()),
+            ("detail", detail.into()),
+            ("range", range.clone()),
+        ])
+    };
+
+    let suggestions = Array::new();
+    for operator in OPERATORS {
+        let item = suggestion(
+            operator.name,
+            COMPLETION_ITEM_KIND_FUNCTION,
+            &operator.signature(),
+        );
+        js::set(&item, "insertText", operator.snippet().into());
+        js::set(&item, "insertTextRules", INSERT_AS_SNIPPET.into());
+        js::set(&item, "documentation", markdown(&operator.documentation()));
+        suggestions.push(&item);
+    }
+    for compute_type in COMPUTE_TYPES {
+        suggestions.push(&suggestion(
+            compute_type,
+            COMPLETION_ITEM_KIND_ENUM_MEMBER,
+            "compute type",
+        ));
+    }
+    for pad_type in PAD_TYPES {
+        suggestions.push(&suggestion(
+            pad_type,
+            COMPLETION_ITEM_KIND_ENUM_MEMBER,
+            "pad type",
+        ));
+    }
+    ENVIRONMENT_SHAPES.with(|shapes| {
+        for variable in shapes.borrow().keys() {
+            suggestions.push(&suggestion(
+                variable,
+                COMPLETION_ITEM_KIND_VARIABLE,
+                "environment variable",
+            ));
+        }
+    });
+
+    js::object(&[("suggestions", suggestions.into())]).into()
+}
+
+/// Describes the inferred shape of the innermost sub-expression of `source`
+/// containing character `offset`, as markdown. This runs on every hover, so
+/// it must not panic, even on the ill-shaped programs seen while typing;
+/// [`shape_inference::infer_known_shapes`] checks the shapes before inferring
+/// them. Nodes which depend on an operator the shape check doesn't cover
+/// have no known shape, but the rest of the program's nodes still do.
+fn describe_shape_at(source: &str, offset: usize) -> Option<String> {
+    let expr = evaluation::parse(source).ok()?;
+    let node = spans::innermost_node(&spans::node_spans(source)?, offset)?;
+    let shapes = ENVIRONMENT_SHAPES
+        .with(|shapes| shape_inference::infer_known_shapes(&expr, &shapes.borrow()));
+    match shapes {
+        Ok(data) => match &data[node] {
+            Some(data) => {
+                shape_inference::describe(data).map(|shape| format!("**Shape:** `{}`", shape))
+            }
+            None => {
+                // Nodes are in post-order, so the first unknown node under
+                // this one is the one the check doesn't cover.
+                let mut subtree = vec![node];
+                let mut i = 0;
+                while i < subtree.len() {
+                    let children = expr.as_ref()[subtree[i]].children();
+                    subtree.extend(children.iter().map(|&child| usize::from(child)));
+                    i += 1;
+                }
+                let uncovered = subtree
+                    .into_iter()
+                    .filter(|&id| data[id].is_none())
+                    .min()
+                    .unwrap();
+                Some(format!(
+                    "**Shape unknown:** {}",
+                    shape_check::unsupported_operator(&expr, uncovered)
+                ))
+            }
+        },
+        Err(e) => Some(format!("**Shape error:** {}", e)),
+    }
+}
+
+/// Implements `HoverProvider.provideHover`, showing the documentation of the
+/// operator under the cursor and the inferred shape of the sub-expression
+/// under the cursor.
+fn provide_hover(model: JsValue, position: JsValue) -> JsValue {
+    let source = js::call_method(&model, "getValue", &[])
+        .as_string()
+        .unwrap_or_default();
+    let offset = js::call_method(&model, "getOffsetAt", &[position])
+        .as_f64()
+        .unwrap_or_default() as usize;
+
+    let atom = spans::atom_at(&source, offset);
+
+    let contents = Array::new();
+    if let Some(operator) = atom.and_then(|span| operators::find_operator(&span.text(&source))) {
+        contents.push(&markdown(&format!("`{}`", operator.signature())));
+        contents.push(&markdown(&operator.documentation()));
+    }
+    if let Some(shape) = describe_shape_at(&source, offset) {
+        contents.push(&markdown(&shape));
+    }
+
+    if contents.length() == 0 {
+        return JsValue::NULL;
+    }
+
+    let span = atom.unwrap_or(Span {
+        start: offset,
+        end: offset + 1,
+    });
+    js::object(&[
+        ("range", editor::range(&source, span)),
+        ("contents", contents.into()),
+    ])
+    .into()
+}
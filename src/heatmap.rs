@@ -0,0 +1,318 @@
+//! A canvas-based heatmap of a tensor.
+//!
+//! The last two dimensions of the tensor form the rows and columns of each
+//! image. Any leading dimensions are tiled into a grid of images, laid out in
+//! the same way as [`AccessPatternView`](crate::access_pattern_view) lays out
+//! iteration dimensions; for an (N, C, H, W) tensor, this gives one row of
+//! per-channel images for each batch element.
+
+use crate::access_pattern_view::{grid_index, grid_shape};
+use crate::result_format::format_dims;
+use ndarray::{ArrayD, IxDyn};
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::events::MouseEvent;
+use yew::{html, Component, ComponentLink, Html, NodeRef, Properties, ShouldRender};
+
+/// Space between tiles, in pixels.
+const GAP: usize = 4;
+/// The size we aim to make each tile, in pixels.
+const TILE_SIZE: usize = 128;
+
+/// The colour scale: values are interpolated between these stops.
+const COLOR_STOPS: [(u8, u8, u8); 3] = [(68, 1, 84), (33, 145, 140), (253, 231, 37)];
+
+/// Maps `value` onto the colour scale, where `min` and `max` are the ends of
+/// the scale.
+pub fn color(value: f64, min: f64, max: f64) -> (u8, u8, u8) {
+    let t = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.5
+    };
+    let scaled = t * (COLOR_STOPS.len() - 1) as f64;
+    let i = (scaled.floor() as usize).min(COLOR_STOPS.len() - 2);
+    let t = scaled - i as f64;
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    let (from, to) = (COLOR_STOPS[i], COLOR_STOPS[i + 1]);
+    (lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
+
+fn css_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({}, {}, {})", r, g, b)
+}
+
+/// Where each element of a tensor is drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    /// The dimensions which are tiled.
+    tile_shape: Vec<usize>,
+    /// The height and width of each tile, in elements.
+    height: usize,
+    width: usize,
+    /// The size of each element, in pixels.
+    scale: usize,
+    /// The number of rows and columns of tiles.
+    rows: usize,
+    columns: usize,
+}
+
+impl Layout {
+    pub fn new(shape: &[usize]) -> Self {
+        let (tile_shape, height, width) = match shape {
+            [] => (vec![], 1, 1),
+            [width] => (vec![], 1, *width),
+            [tile_shape @ .., height, width] => (tile_shape.to_vec(), *height, *width),
+        };
+        let (rows, columns) = grid_shape(&tile_shape);
+        Layout {
+            scale: (TILE_SIZE / height.max(width).max(1)).max(1),
+            tile_shape,
+            height,
+            width,
+            rows,
+            columns,
+        }
+    }
+
+    fn tile_width(&self) -> usize {
+        self.width * self.scale + GAP
+    }
+
+    fn tile_height(&self) -> usize {
+        self.height * self.scale + GAP
+    }
+
+    /// The size of the canvas needed, in pixels.
+    pub fn canvas_size(&self) -> (usize, usize) {
+        (
+            self.columns * self.tile_width(),
+            self.rows * self.tile_height(),
+        )
+    }
+
+    /// Finds the index of the element drawn at pixel (`x`, `y`), if any.
+    pub fn locate(&self, x: usize, y: usize) -> Option<Vec<usize>> {
+        let (row, column) = (y / self.tile_height(), x / self.tile_width());
+        let (y, x) = (
+            (y % self.tile_height()) / self.scale,
+            (x % self.tile_width()) / self.scale,
+        );
+        if row >= self.rows || column >= self.columns || y >= self.height || x >= self.width {
+            return None;
+        }
+        Some(self.index(row, column, y, x))
+    }
+
+    /// The index of element (`y`, `x`) of the given tile. Scalars and vectors
+    /// are drawn as a single row, so their indices have leading zeros which
+    /// aren't in the tensor.
+    fn index(&self, row: usize, column: usize, y: usize, x: usize) -> Vec<usize> {
+        let mut index = grid_index(&self.tile_shape, row, column);
+        index.push(y);
+        index.push(x);
+        index
+    }
+}
+
+#[derive(Properties, Clone)]
+pub struct HeatmapProps {
+    pub tensor: Rc<ArrayD<f64>>,
+}
+
+pub struct Heatmap {
+    props: HeatmapProps,
+    link: ComponentLink<Self>,
+    canvas: NodeRef,
+    layout: Layout,
+    /// The smallest and largest (non-NaN) values in the tensor.
+    range: (f64, f64),
+    /// Whether the tensor has changed since the canvas was last drawn.
+    needs_redraw: bool,
+    /// The index of the element under the mouse, as laid out by [`Layout`],
+    /// and the mouse's position over the canvas.
+    hovered: Option<(Vec<usize>, i32, i32)>,
+}
+
+pub enum HeatmapMessage {
+    Hover(Option<(Vec<usize>, i32, i32)>),
+}
+
+/// The smallest and largest (non-NaN) values in `tensor`.
+fn range(tensor: &ArrayD<f64>) -> (f64, f64) {
+    tensor
+        .iter()
+        .filter(|v| !v.is_nan())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+impl Heatmap {
+    /// Converts an index as laid out by [`Layout`] into an index into the
+    /// tensor, dropping the extra dimensions added for scalars and vectors.
+    fn tensor_index(&self, index: &[usize]) -> Vec<usize> {
+        index[index.len() - self.props.tensor.ndim()..].to_vec()
+    }
+
+    fn draw(&self) {
+        let canvas = match self.canvas.cast::<HtmlCanvasElement>() {
+            Some(canvas) => canvas,
+            None => return,
+        };
+        let context: CanvasRenderingContext2d =
+            canvas.get_context("2d").unwrap().unwrap().unchecked_into();
+        let (width, height) = self.layout.canvas_size();
+        context.clear_rect(0.0, 0.0, width as f64, height as f64);
+
+        let (min, max) = self.range;
+        let layout = &self.layout;
+        for row in 0..layout.rows {
+            for column in 0..layout.columns {
+                for y in 0..layout.height {
+                    for x in 0..layout.width {
+                        let index = self.tensor_index(&layout.index(row, column, y, x));
+                        let value = self.props.tensor[IxDyn(&index)];
+                        context
+                            .set_fill_style(&JsValue::from_str(&css_color(color(value, min, max))));
+                        context.fill_rect(
+                            (column * layout.tile_width() + x * layout.scale) as f64,
+                            (row * layout.tile_height() + y * layout.scale) as f64,
+                            layout.scale as f64,
+                            layout.scale as f64,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Component for Heatmap {
+    type Message = HeatmapMessage;
+    type Properties = HeatmapProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            layout: Layout::new(props.tensor.shape()),
+            range: range(&props.tensor),
+            needs_redraw: true,
+            props,
+            link,
+            canvas: NodeRef::default(),
+            hovered: None,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            HeatmapMessage::Hover(index) => {
+                let changed = index != self.hovered;
+                self.hovered = index;
+                changed
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if Rc::ptr_eq(&props.tensor, &self.props.tensor) {
+            return false;
+        }
+        self.layout = Layout::new(props.tensor.shape());
+        self.range = range(&props.tensor);
+        self.props = props;
+        self.hovered = None;
+        self.needs_redraw = true;
+        true
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        // Hovering re-renders the tooltip, but leaves the canvas as it is.
+        if self.needs_redraw {
+            self.draw();
+            self.needs_redraw = false;
+        }
+    }
+
+    fn view(&self) -> Html {
+        let (width, height) = self.layout.canvas_size();
+        let (min, max) = self.range;
+        let tooltip = match &self.hovered {
+            Some((index, x, y)) => {
+                let index = self.tensor_index(index);
+                html! {
+                    <div style={format!(
+                        "position: absolute; left: {}px; top: {}px; pointer-events: none; \
+                         background-color: #fff; border: 1px solid #888; padding: 2px",
+                        x + 12,
+                        y + 12
+                    )}>
+                        {format!("({}): {:.4}", format_dims(&index), self.props.tensor[IxDyn(&index)])}
+                    </div>
+                }
+            }
+            None => html! {},
+        };
+
+        let layout = self.layout.clone();
+        html! {
+            <div>
+                <div style={"position: relative; display: inline-block"}>
+                    <canvas ref=self.canvas.clone()
+                        width={width.to_string()}
+                        height={height.to_string()}
+                        onmousemove=self.link.callback(move |e: MouseEvent| {
+                            let (x, y) = (e.offset_x(), e.offset_y());
+                            HeatmapMessage::Hover(
+                                layout
+                                    .locate(x.max(0) as usize, y.max(0) as usize)
+                                    .map(|index| (index, x, y)),
+                            )
+                        })
+                        onmouseleave=self.link.callback(|_| HeatmapMessage::Hover(None)) />
+                    {tooltip}
+                </div>
+                <div style={"display: flex; align-items: center"}>
+                    {format!("{:.2}", min)}
+                    <div style={format!(
+                        "width: 200px; height: 12px; margin: 0 8px; background: linear-gradient(to right, {}, {}, {})",
+                        css_color(COLOR_STOPS[0]),
+                        css_color(COLOR_STOPS[1]),
+                        css_color(COLOR_STOPS[2]),
+                    )} />
+                    {format!("{:.2}", max)}
+                </div>
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(color(0.0, 0.0, 1.0), COLOR_STOPS[0]);
+        assert_eq!(color(0.5, 0.0, 1.0), COLOR_STOPS[1]);
+        assert_eq!(color(1.0, 0.0, 1.0), COLOR_STOPS[2]);
+        assert_eq!(color(3.0, 0.0, 1.0), COLOR_STOPS[2]);
+        assert_eq!(color(3.0, 3.0, 3.0), COLOR_STOPS[1]);
+    }
+
+    #[test]
+    fn layout() {
+        // A batch of one, with three 2x2 channels.
+        let layout = Layout::new(&[1, 3, 2, 2]);
+        let tile = 2 * (TILE_SIZE / 2) + GAP;
+        assert_eq!(layout.canvas_size(), (3 * tile, tile));
+        assert_eq!(layout.locate(0, 0), Some(vec![0, 0, 0, 0]));
+        assert_eq!(
+            layout.locate(tile + TILE_SIZE / 2, 0),
+            Some(vec![0, 1, 0, 1])
+        );
+        // In the gap between tiles.
+        assert_eq!(layout.locate(tile - 1, 0), None);
+    }
+}
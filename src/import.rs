@@ -0,0 +1,166 @@
+//! Importing tensors from files and pasted text: NumPy's `.npy` and `.npz`
+//! files (see [`crate::npy`]), CSV, as exported by spreadsheets, and JSON
+//! nested arrays.
+
+use crate::npy;
+use crate::result_format::format_dims;
+use crate::tensor_literal;
+use ndarray::{ArrayD, IxDyn};
+
+/// Parses a 2D tensor from CSV, one row per line. Values can be separated by
+/// commas or, as spreadsheets copy them, by tabs. A first line containing no
+/// numbers is taken to be a header, and skipped.
+pub fn parse_csv(source: &str) -> Result<ArrayD<f64>, String> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let cells = line
+                .split(&[',', '\t'][..])
+                .map(str::trim)
+                .collect::<Vec<_>>();
+            // Lines are numbered from 1, as in editors.
+            (i + 1, cells)
+        })
+        .peekable();
+
+    if let Some((_, cells)) = lines.peek() {
+        if cells.iter().all(|cell| cell.parse::<f64>().is_err()) {
+            lines.next();
+        }
+    }
+
+    let mut columns = None;
+    let mut rows = 0;
+    let mut values = vec![];
+    for (line, cells) in lines {
+        match columns {
+            None => columns = Some((line, cells.len())),
+            Some((first_line, len)) if len != cells.len() => {
+                return Err(format!(
+                    "line {} has {} values, but line {} has {}",
+                    line,
+                    cells.len(),
+                    first_line,
+                    len
+                ))
+            }
+            Some(_) => (),
+        }
+        for cell in cells {
+            values.push(
+                cell.parse()
+                    .map_err(|_| format!("invalid number '{}' on line {}", cell, line))?,
+            );
+        }
+        rows += 1;
+    }
+
+    let (_, columns) = columns.ok_or_else(|| "the CSV has no rows".to_string())?;
+    Ok(ArrayD::from_shape_vec(IxDyn(&[rows, columns]), values).unwrap())
+}
+
+/// Parses a tensor from a JSON nested array, e.g. `[[1, 2], [3, 4]]`. JSON
+/// arrays of numbers are also tensor literals, so this is just
+/// [`tensor_literal::parse_tensor_literal`].
+pub fn parse_json(source: &str) -> Result<ArrayD<f64>, String> {
+    tensor_literal::parse_tensor_literal(source)
+}
+
+/// Parses CSV, as in [`parse_csv`], and reshapes it to `shape`. The values
+/// are taken in row-major order, so, for example, a single line of CSV can
+/// fill a tensor of any shape with the right number of elements.
+pub fn parse_csv_with_shape(source: &str, shape: &[usize]) -> Result<ArrayD<f64>, String> {
+    let tensor = parse_csv(source)?;
+    let len = shape
+        .iter()
+        .try_fold(1usize, |len, &dim| len.checked_mul(dim))
+        .ok_or_else(|| format!("the shape given, ({}), is too large", format_dims(shape)))?;
+    if tensor.len() != len {
+        return Err(format!(
+            "the CSV has {} values ({} rows of {}), but the shape given, ({}), needs {}",
+            tensor.len(),
+            tensor.shape()[0],
+            tensor.shape()[1],
+            format_dims(shape),
+            len
+        ));
+    }
+    Ok(tensor.into_shape(IxDyn(shape)).unwrap())
+}
+
+/// Parses a `.npy`, `.npz`, `.csv` or `.json` file called `file_name`.
+/// Tensors from every format but `.npz`, which names its own, are named after
+/// the file.
+pub fn parse_file(file_name: &str, bytes: &[u8]) -> Result<Vec<(String, ArrayD<f64>)>, String> {
+    let text =
+        || std::str::from_utf8(bytes).map_err(|_| format!("{} isn't valid UTF-8 text", file_name));
+    if let Some(name) = file_name.strip_suffix(".npy") {
+        Ok(vec![(name.to_string(), npy::parse_npy(bytes)?)])
+    } else if file_name.ends_with(".npz") {
+        npy::parse_npz(bytes)
+    } else if let Some(name) = file_name.strip_suffix(".csv") {
+        Ok(vec![(name.to_string(), parse_csv(text()?)?)])
+    } else if let Some(name) = file_name.strip_suffix(".json") {
+        Ok(vec![(name.to_string(), parse_json(text()?)?)])
+    } else {
+        Err(format!(
+            "{} is not a .npy, .npz, .csv or .json file",
+            file_name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        let expected =
+            ArrayD::from_shape_vec(IxDyn(&[2, 3]), vec![1., 2., 3., 4., 5., 6.]).unwrap();
+        assert_eq!(parse_csv("1,2,3\n4,5,6\n").unwrap(), expected);
+        // Copied from a spreadsheet, with a header.
+        assert_eq!(
+            parse_csv("a\tb\tc\r\n1\t2\t3\r\n4\t5\t6").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_csv_with_shape("1, 2, 3, 4, 5, 6", &[3, 2]).unwrap(),
+            expected.into_shape(IxDyn(&[3, 2])).unwrap()
+        );
+        assert_eq!(
+            parse_file("t.json", b"[1, 2]").unwrap(),
+            vec![(
+                "t".to_string(),
+                ArrayD::from_shape_vec(IxDyn(&[2]), vec![1., 2.]).unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_csv("1,2,3\n\n4,5").unwrap_err(),
+            "line 3 has 2 values, but line 1 has 3"
+        );
+        assert_eq!(
+            parse_csv("1,2\n3,x").unwrap_err(),
+            "invalid number 'x' on line 2"
+        );
+        assert_eq!(parse_csv(" \n").unwrap_err(), "the CSV has no rows");
+        assert_eq!(
+            parse_csv_with_shape("1,2,3\n4,5,6", &[2, 2]).unwrap_err(),
+            "the CSV has 6 values (2 rows of 3), but the shape given, (2, 2), needs 4"
+        );
+        assert_eq!(
+            parse_csv_with_shape("1,2", &[usize::MAX, 2]).unwrap_err(),
+            format!("the shape given, ({}, 2), is too large", usize::MAX)
+        );
+        assert_eq!(
+            parse_file("a.txt", &[]).unwrap_err(),
+            "a.txt is not a .npy, .npz, .csv or .json file"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+//! Small helpers for building the plain JavaScript objects Monaco expects.
+
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Builds a JavaScript object with the given properties.
+pub fn object(properties: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in properties {
+        set(&object, key, value.clone());
+    }
+    object
+}
+
+/// Sets the property `key` of `target` to `value`.
+pub fn set(target: &JsValue, key: &str, value: JsValue) {
+    Reflect::set(target, &JsValue::from_str(key), &value).unwrap();
+}
+
+/// Builds a JavaScript array of strings.
+pub fn string_array<'a>(strings: impl IntoIterator<Item = &'a str>) -> Array {
+    strings.into_iter().map(JsValue::from_str).collect()
+}
+
+/// Gets the property `key` of `target`.
+pub fn get(target: &JsValue, key: &str) -> JsValue {
+    Reflect::get(target, &JsValue::from_str(key)).unwrap()
+}
+
+/// Calls the method `name` of `target` with `arguments`.
+pub fn call_method(target: &JsValue, name: &str, arguments: &[JsValue]) -> JsValue {
+    let method: Function = get(target, name).unchecked_into();
+    Reflect::apply(&method, target, &arguments.iter().collect::<Array>()).unwrap()
+}
@@ -1,20 +1,44 @@
 #![recursion_limit = "1024"]
 
-use glenside::language::interpreter::Environment;
+mod access_pattern_view;
+mod download;
+mod editor;
+mod editor_markers;
+mod evaluation;
+mod glenside_language;
+mod heatmap;
+mod import;
+mod js;
+mod notebook;
+mod npy;
+mod operators;
+mod program_tree;
+mod result_format;
+mod shape_check;
+mod shape_inference;
+mod spans;
+mod tensor_inspector;
+mod tensor_literal;
+mod tensor_spec;
+mod value_generation;
+mod workspace;
+
+use glenside::language::interpreter::{Environment, Value};
 use lazy_static::lazy_static;
 use monaco::{
     api::CodeEditorOptions,
     sys::editor::BuiltinTheme,
     yew::{CodeEditor, CodeEditorLink},
 };
-use ndarray::{ArrayD, Dimension, IxDyn};
-use rand::{
-    distributions::{Distribution, Uniform},
-    rngs::OsRng,
-};
-use std::collections::HashMap;
+use ndarray::ArrayD;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use tensor_spec::TensorSpec;
+use value_generation::ValueGenerationStrategy;
 use wasm_bindgen::prelude::*;
+use workspace::{ProgramTab, Workspace};
+use yew::services::reader::{File, FileData, ReaderService, ReaderTask};
+use yew::services::Task;
 use yew::{
     html, html_nested, ChangeData, Component, ComponentLink, Html, InputData, Properties,
     ShouldRender,
@@ -24,13 +48,57 @@ fn get_options() -> CodeEditorOptions {
     CodeEditorOptions::default()
         .with_new_dimension(500, 500)
         .with_builtin_theme(BuiltinTheme::VsDark)
+        .with_language(glenside_language::LANGUAGE_ID.to_string())
+}
+
+/// The tensors in the environment, by name. Unlike the interpreter's
+/// [`Environment`], this owns its names, so they can change without leaking
+/// memory. It's converted with [`interpreter_environment`] when a program is
+/// evaluated. The tensors are shared with their inspectors, and between the
+/// environments switched between, rather than copied.
+type TensorEnvironment = HashMap<String, Rc<ArrayD<f64>>>;
+
+/// Converts `environment` into the form the interpreter takes.
+fn interpreter_environment(environment: &TensorEnvironment) -> Environment<'_, f64> {
+    environment
+        .iter()
+        .map(|(name, value)| (name.as_str(), (**value).clone()))
+        .collect()
 }
 
 struct Example<'a> {
     name: &'a str,
     description: &'a str,
     glenside_source: &'a str,
-    environment: Environment<'a, f64>,
+    /// The names and shapes of the tensors in the example's environment. Their
+    /// values are generated from the global seed; see
+    /// [`Example::environment`].
+    environment_shapes: Vec<(&'a str, Vec<usize>)>,
+}
+
+impl<'a> Example<'a> {
+    /// The specs of the tensors in the example's environment, which are filled
+    /// with random values.
+    fn environment_specs(&self) -> Vec<TensorSpec> {
+        self.environment_shapes
+            .iter()
+            .map(|(name, shape)| TensorSpec::random(name, shape))
+            .collect()
+    }
+
+    /// Builds the example's environment, filling each tensor with values drawn
+    /// uniformly from [-2, 2). The same `seed` always gives the same values.
+    fn environment(&self, seed: u64) -> TensorEnvironment {
+        specs_environment(&self.environment_specs(), seed)
+    }
+}
+
+/// Builds the tensors described by `specs`, leaving out any which are invalid.
+fn specs_environment(specs: &[TensorSpec], seed: u64) -> TensorEnvironment {
+    specs
+        .iter()
+        .filter_map(|spec| Some((spec.name.clone(), Rc::new(spec.value(seed)?))))
+        .collect()
 }
 
 lazy_static! {
@@ -38,16 +106,7 @@ lazy_static! {
         name: "A single tensor",
         description: "Tensors are first-class citizens in Glenside. Currently, tensors are defined simply by their shape.",
         glenside_source: "t",
-        environment: {
-            let mut env = HashMap::new();
-            env.insert(
-                "t",
-                ArrayD::from_shape_fn(IxDyn(&[2,3]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env
-        },
+        environment_shapes: vec![("t", vec![2, 3])],
     };
 }
 
@@ -62,16 +121,7 @@ The first tuple in the access pattern's shape describes the overall shape of the
 The second tuple in the access pattern's shape describes the shape of each subview being accessed—in this case, (2, 3).
 Thus, this access pattern conveys that we are viewing our (2, 3) tensor as a series of subviews of shape (2, 3), of which there are only one.",
         glenside_source: "(access-tensor t)",
-        environment: {
-            let mut env = HashMap::new();
-            env.insert(
-                "t",
-                ArrayD::from_shape_fn(IxDyn(&[2, 3]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env
-        },
+        environment_shapes: vec![("t", vec![2, 3])],
     };
 }
 
@@ -83,16 +133,7 @@ lazy_static! {
         With this access pattern, we are viewing the tensor t as a list/vector
         of length 2, whose items are vectors of length 3.",
         glenside_source: "(access (access-tensor t) 1)",
-        environment: {
-            let mut env = HashMap::new();
-            env.insert(
-                "t",
-                ArrayD::from_shape_fn(IxDyn(&[2, 3]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env
-        },
+        environment_shapes: vec![("t", vec![2, 3])],
     };
 }
 
@@ -101,16 +142,7 @@ lazy_static! {
         name: "Computing over Access Patterns",
         description: "",
         glenside_source: "(compute reduce-sum (access (access-tensor t) 1))",
-        environment: {
-            let mut env = HashMap::new();
-            env.insert(
-                "t",
-                ArrayD::from_shape_fn(IxDyn(&[2, 3]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env
-        },
+        environment_shapes: vec![("t", vec![2, 3])],
     };
 }
 
@@ -140,22 +172,10 @@ lazy_static! {
     3)))
  (list 1 0 2 3))
 "#,
-        environment: {
-            let mut env = HashMap::new();
-            env.insert(
-                "activations",
-                ArrayD::from_shape_fn(IxDyn(&[1, 3, 32, 32]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env.insert(
-                "weights",
-                ArrayD::from_shape_fn(IxDyn(&[8, 3, 3, 3]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env
-        },
+        environment_shapes: vec![
+            ("activations", vec![1, 3, 32, 32]),
+            ("weights", vec![8, 3, 3, 3]),
+        ],
     };
 }
 
@@ -196,22 +216,7 @@ Finally,
     (access-tensor b)
     (list 1 0))
    1)))"#,
-        environment: {
-            let mut env = HashMap::new();
-            env.insert(
-                "b",
-                ArrayD::from_shape_fn(IxDyn(&[3, 4]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env.insert(
-                "a",
-                ArrayD::from_shape_fn(IxDyn(&[2, 3]), |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            );
-            env
-        },
+        environment_shapes: vec![("b", vec![3, 4]), ("a", vec![2, 3])],
     };
 }
 
@@ -228,146 +233,744 @@ lazy_static! {
 
 enum Message {
     NewInput,
+    CheckShapes,
+    Trace,
+    /// Show the given step of the current [`App::trace`].
+    TraceStep(usize),
+    /// The editor's cursor moved to the given character offset.
+    CursorMoved(usize),
+    /// The user clicked on the node with the given id in the program tree.
+    NodeSelected(usize),
     EnvironmentValueUpdated(String, ArrayD<f64>),
+    /// The tensor with the given name was removed from the environment.
+    EnvironmentValueRemoved(String),
+    /// One of the notebook's cells bound its result to the given name.
+    BindingUpdated(String, ArrayD<f64>),
+    /// The notebook's binding with the given name was removed.
+    BindingRemoved(String),
+    /// One of the pre-set environment's inputs, rather than one of the user's
+    /// own, has a new value.
+    PreSetValueUpdated(String, ArrayD<f64>),
+    /// The tensor with the given name was removed from the pre-set
+    /// environment.
+    PreSetValueRemoved(String),
     ExampleSelected(Option<usize>),
+    SeedUpdated(u64),
+    /// Download the result tensor as a `.npy` file.
+    DownloadResult,
+    /// Download the environment, along with the result tensor if there is
+    /// one, as a `.npz` file.
+    DownloadEnvironment,
+    /// The specs of the example's environment inputs and of the user's own
+    /// inputs, respectively, changed.
+    EnvironmentSpecsUpdated(Vec<TensorSpec>, Vec<TensorSpec>),
+    /// Make a permalink to the workspace, to be copied by the user.
+    CreatePermalink,
+    /// The text in the editor changed.
+    ProgramEdited,
+    /// Throw away the saved workspace and start afresh.
+    ResetWorkspace,
+    /// Show the tab with the given index in the editor.
+    TabSelected(usize),
+    /// Open a new, empty tab.
+    TabAdded,
+    /// Close the tab with the given index.
+    TabClosed(usize),
+    /// Rename the current tab.
+    TabRenamed(String),
+    /// Switch between the editor and the notebook of cells.
+    CellModeToggled,
+}
+
+#[derive(Properties, Clone)]
+struct AppProps {
+    /// The workspace to start with, e.g. from a permalink. If there isn't
+    /// one, the workspace saved in local storage is restored.
+    #[prop_or_default]
+    workspace: Option<Workspace>,
+}
+
+/// A program open in one of the editor's tabs, along with its result.
+struct Tab {
+    name: String,
+    /// The tab's program. For the current tab, this is only as up to date as
+    /// when the tab was switched to; the editor has the latest text.
+    program: String,
+    result_text: String,
+    /// The tensor produced by the last interpreted program, if it produced
+    /// one, along with its access axis if it was an access pattern.
+    result_tensor: Option<(Rc<ArrayD<f64>>, Option<usize>)>,
+}
+
+impl Tab {
+    fn new(name: String, program: String) -> Self {
+        Self {
+            name,
+            program,
+            result_text: String::default(),
+            result_tensor: None,
+        }
+    }
 }
 
 struct App {
     link: ComponentLink<Self>,
     code_editor_link: CodeEditorLink,
-    result_text: String,
-    environment: Environment<'static, f64>,
-    /// Stores whatever the user has typed into the editor. Used when switching
-    /// back and forth between examples, so we can save/restore whatever the
-    /// user has typed.
-    user_editor_state: String,
+    /// The options the editor is created with. These never change, as the
+    /// editor would be recreated if they did; tabs are switched by setting
+    /// the editor's text instead.
+    editor_options: Rc<CodeEditorOptions>,
+    /// The programs open in the editor. There's always at least one.
+    tabs: Vec<Tab>,
+    /// The index into [`App::tabs`] of the tab shown in the editor.
+    current_tab: usize,
+    /// Whether the notebook of cells is shown instead of the editor.
+    cell_mode: bool,
+    /// The environment programs are evaluated in. It's shared with the
+    /// notebook, so it's only copied when it changes while the notebook still
+    /// has the old one; see [`App::environment_mut`].
+    environment: Rc<TensorEnvironment>,
     /// Stores whatever environment the user has entered. Used when switching
     /// back and forth between examples, so that we can save/restore the user's
     /// environment.
-    user_environment_state: Environment<'static, f64>,
+    user_environment_state: TensorEnvironment,
+    /// The names in [`App::user_environment_state`] bound by the notebook's
+    /// cells. The cells aren't saved, so neither are their bindings.
+    notebook_bindings: HashSet<String>,
     example_selected: Option<usize>,
+    /// The specs the example's environment inputs were created from.
+    pre_set_environment: Vec<TensorSpec>,
+    /// The current specs of the example's environment inputs, which may have
+    /// been edited since they were created.
+    example_environment_specs: Vec<TensorSpec>,
+    /// The current specs of the user's own environment inputs.
+    user_environment_specs: Vec<TensorSpec>,
+    /// The seed from which all random tensors are generated.
+    seed: u64,
+    /// The last permalink made, to be copied by the user.
+    permalink: Option<String>,
+    /// The names of the tensors too large to be included in
+    /// [`App::permalink`].
+    permalink_left_out: Vec<String>,
+    /// The value of every node of the program, recorded by the step-through
+    /// debugger.
+    trace: Option<evaluation::Trace>,
+    /// The program [`App::trace`] was recorded from.
+    trace_source: String,
+    /// The step of [`App::trace`] currently shown, i.e. the id of the node
+    /// whose value is shown.
+    trace_step: usize,
+    /// The program in the editor, as shown by the program tree.
+    program_tree: Rc<Vec<program_tree::TreeNode>>,
+    /// The program [`App::program_tree`] was built from.
+    program_tree_source: String,
+    /// The id of the node under the editor's cursor.
+    selected_node: Option<usize>,
 }
+impl App {
+    /// [`App::environment`], to be changed.
+    fn environment_mut(&mut self) -> &mut TensorEnvironment {
+        Rc::make_mut(&mut self.environment)
+    }
+
+    /// Should be called whenever [`App::environment`] changes.
+    fn environment_changed(&self) {
+        glenside_language::set_environment_shapes(self.environment_shapes());
+    }
+
+    /// The shapes of the tensors in [`App::environment`].
+    fn environment_shapes(&self) -> HashMap<String, Vec<usize>> {
+        self.environment
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.shape().to_vec()))
+            .collect()
+    }
+
+    /// Renders the step-through debugger's view of [`App::trace`].
+    fn view_trace(&self) -> Html {
+        let trace = match &self.trace {
+            Some(trace) => trace,
+            None => return html! {},
+        };
+        let step = self.trace_step;
+        let num_steps = trace.values.len();
+
+        html! {
+            <div>
+                <input type={"button"} value={"previous"} disabled={step == 0}
+                    onclick=self.link.callback(move |_| Message::TraceStep(step.saturating_sub(1))) />
+                <input type={"button"} value={"next"} disabled={step + 1 == num_steps}
+                    onclick=self.link.callback(move |_| Message::TraceStep((step + 1).min(num_steps - 1))) />
+                {format!(" step {} of {}: ", step + 1, num_steps)}
+                <code>{trace.expr.as_ref()[step].to_string()}</code>
+                <br/>
+                <textarea
+                    style={"width:500px; height:100px"}
+                    readonly={true}>
+                    {format!(
+                        "value of {}:\n{}",
+                        trace.spans[step].text(&self.trace_source),
+                        result_format::format_value(&trace.values[step]),
+                    )}</textarea>
+                {
+                    match &trace.tensors[step] {
+                        Some(tensor) => html! {
+                            <tensor_inspector::TensorInspector tensor=tensor.clone() />
+                        },
+                        None => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+
+    /// Renders the editor's tabs, with buttons to switch between, add and
+    /// close them, and a text box renaming the current tab.
+    fn view_tabs(&self) -> Html {
+        let closable = self.tabs.len() > 1;
+        html! {
+            <div>
+                { for self.tabs.iter().enumerate().map(|(i, tab)| html! {
+                    <span>
+                        <input type={"button"} value={&tab.name} disabled={i == self.current_tab}
+                            onclick=self.link.callback(move |_| Message::TabSelected(i)) />
+                        {
+                            if closable {
+                                html! {
+                                    <input type={"button"} value={"×"} title={"close"}
+                                        onclick=self.link.callback(move |_| Message::TabClosed(i)) />
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {" "}
+                    </span>
+                }) }
+                <input type={"button"} value={"+"} title={"new tab"}
+                    onclick=self.link.callback(|_| Message::TabAdded) />
+                <br/>
+                <label>
+                    {"Tab name: "}
+                    <input type={"text"} value={&self.tab().name}
+                        oninput=self.link.callback(|event: InputData| Message::TabRenamed(event.value)) />
+                </label>
+            </div>
+        }
+    }
+
+    /// Renders a collapsible inspector for each tensor in
+    /// [`App::environment`].
+    fn view_environment(&self) -> Html {
+        let mut names = self.environment.keys().collect::<Vec<_>>();
+        names.sort();
+        html! {
+            <div>
+                { for names.into_iter().map(|name| {
+                    let value = &self.environment[name];
+                    html! {
+                        <details>
+                            <summary>
+                                <code>{name}</code>
+                                {format!(" ({})", result_format::format_dims(value.shape()))}
+                            </summary>
+                            <tensor_inspector::TensorInspector tensor=value.clone() />
+                        </details>
+                    }
+                }) }
+            </div>
+        }
+    }
+
+    /// The program currently in the editor.
+    fn editor_text(&self) -> String {
+        self.code_editor_link
+            .with_editor(|editor| editor.get_model().unwrap().get_value())
+            .unwrap()
+    }
+
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.current_tab]
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.current_tab]
+    }
+
+    /// Shows the tab with index `i` in the editor, keeping the text of the
+    /// tab being switched away from.
+    fn switch_tab(&mut self, i: usize) {
+        let text = self.editor_text();
+        self.tab_mut().program = text;
+        self.current_tab = i;
+        let program = &self.tabs[i].program;
+        self.code_editor_link.with_editor(|editor| {
+            let model = editor.get_model().unwrap();
+            model.set_value(program);
+            editor_markers::set_error_markers(editor, program, None);
+        });
+        // The trace is of the other tab's program.
+        self.trace = None;
+    }
+
+    /// A name for a new tab which no other tab has.
+    fn new_tab_name(&self) -> String {
+        (1..)
+            .map(|i| format!("program {}", i))
+            .find(|name| self.tabs.iter().all(|tab| tab.name != *name))
+            .unwrap()
+    }
+
+    /// A snapshot of the workspace, from which it can be restored, along with
+    /// the names of the tensors in the user's environment which are too
+    /// large to be part of it.
+    fn workspace(&self) -> (Workspace, Vec<String>) {
+        let current_program = self.editor_text();
+        let mut workspace = Workspace {
+            tabs: self
+                .tabs
+                .iter()
+                .enumerate()
+                .map(|(i, tab)| ProgramTab {
+                    name: tab.name.clone(),
+                    program: if i == self.current_tab {
+                        current_program.clone()
+                    } else {
+                        tab.program.clone()
+                    },
+                })
+                .collect(),
+            current_tab: self.current_tab,
+            example: self.example_selected.map(|i| EXAMPLES[i].name.to_string()),
+            example_environment: self.example_environment_specs.clone(),
+            environment: self.user_environment_specs.clone(),
+            seed: self.seed,
+        };
+
+        // Imported tensors have no specs, so their values are saved instead.
+        let mut unspecified = self
+            .user_environment_state
+            .iter()
+            .filter(|(name, _)| !self.has_spec(name) && !self.notebook_bindings.contains(*name))
+            .map(|(name, value)| (name.as_str(), &**value))
+            .collect::<Vec<_>>();
+        unspecified.sort_by_key(|(name, _)| *name);
+        let left_out = workspace.add_explicit_tensors(unspecified);
+
+        (workspace, left_out)
+    }
+
+    /// Whether the tensor called `name` in the user's environment comes from
+    /// one of their environment inputs, and so is saved as its spec.
+    fn has_spec(&self, name: &str) -> bool {
+        self.user_environment_specs
+            .iter()
+            .any(|spec| spec.name == name)
+    }
+
+    /// Saves the workspace in local storage, so that it survives reloads.
+    fn save_workspace(&self) {
+        self.workspace().0.save();
+    }
+}
+
 impl Component for App {
     type Message = Message;
-    type Properties = ();
+    type Properties = AppProps;
 
-    fn create(_props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Self {
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let workspace = props.workspace.or_else(Workspace::load).unwrap_or_default();
+        let example_selected = workspace
+            .example
+            .as_ref()
+            .and_then(|name| EXAMPLES.iter().position(|example| example.name == name));
+        let user_environment_state = specs_environment(&workspace.environment, workspace.seed);
+        let environment = match example_selected {
+            Some(_) => specs_environment(&workspace.example_environment, workspace.seed),
+            None => user_environment_state.clone(),
+        };
+        let mut tabs = workspace
+            .tabs
+            .into_iter()
+            .map(|tab| Tab::new(tab.name, tab.program))
+            .collect::<Vec<_>>();
+        if tabs.is_empty() {
+            tabs.push(Tab::new("program 1".to_string(), String::default()));
+        }
+        let current_tab = workspace.current_tab.min(tabs.len() - 1);
+
+        let app = Self {
             link: link,
             code_editor_link: CodeEditorLink::default(),
-            result_text: String::default(),
-            environment: Environment::default(),
-            user_editor_state: String::default(),
-            user_environment_state: Environment::default(),
-            example_selected: None,
+            editor_options: Rc::new(get_options().with_value(tabs[current_tab].program.clone())),
+            tabs,
+            current_tab,
+            cell_mode: false,
+            environment: Rc::new(environment),
+            user_environment_state,
+            notebook_bindings: HashSet::new(),
+            example_selected,
+            pre_set_environment: workspace.example_environment.clone(),
+            example_environment_specs: workspace.example_environment,
+            user_environment_specs: workspace.environment,
+            seed: workspace.seed,
+            permalink: None,
+            permalink_left_out: vec![],
+            trace: None,
+            trace_source: String::default(),
+            trace_step: 0,
+            program_tree: Rc::default(),
+            program_tree_source: String::default(),
+            selected_node: None,
+        };
+        app.environment_changed();
+        app
+    }
+
+    fn rendered(&mut self, first_render: bool) {
+        if first_render {
+            let callback = self.link.callback(Message::CursorMoved);
+            self.code_editor_link
+                .with_editor(|editor| editor::on_cursor_moved(editor, callback));
+            let callback = self.link.callback(|_| Message::ProgramEdited);
+            self.code_editor_link
+                .with_editor(|editor| editor::on_content_changed(editor, callback));
+
+            // Only now that the app has been built from the workspace is it
+            // safe to save, e.g. over the saved workspace with a permalink's.
+            // Had building it crashed, reloading would only crash again.
+            self.save_workspace();
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Message::EnvironmentValueUpdated(name, value) => {
-                let name = Box::leak(name.into_boxed_str());
-                self.user_environment_state.insert(name, value.clone());
-                self.environment.insert(name, value);
-                false
+                // Any binding of the same name has been replaced.
+                self.notebook_bindings.remove(&name);
+                let value = Rc::new(value);
+                self.user_environment_state
+                    .insert(name.clone(), value.clone());
+                // Tensors with specs are saved when their specs change.
+                if !self.has_spec(&name) {
+                    self.save_workspace();
+                }
+                self.environment_mut().insert(name, value);
+                self.environment_changed();
+                // Update the environment's inspectors.
+                true
             }
-            Message::NewInput => {
-                let text_input = self
-                    .code_editor_link
-                    .with_editor(|editor| editor.get_model().unwrap().get_value())
-                    .unwrap();
-
-                // Quickly handle the case where there's no program specified.
-                // TODO we don't need this if we can handle parsing errors.
-                if text_input.is_empty() {
-                    return false;
+            Message::EnvironmentValueRemoved(name) => {
+                self.user_environment_state.remove(&name);
+                if !self.has_spec(&name) {
+                    self.save_workspace();
                 }
+                self.environment_mut().remove(&name);
+                self.environment_changed();
+                true
+            }
+            Message::BindingUpdated(name, value) => {
+                let value = Rc::new(value);
+                self.notebook_bindings.insert(name.clone());
+                self.user_environment_state
+                    .insert(name.clone(), value.clone());
+                self.environment_mut().insert(name, value);
+                self.environment_changed();
+                true
+            }
+            Message::BindingRemoved(name) => {
+                self.notebook_bindings.remove(&name);
+                self.user_environment_state.remove(&name);
+                self.environment_mut().remove(&name);
+                self.environment_changed();
+                true
+            }
+            // Edits to an example's environment shouldn't find their way into
+            // the user's own.
+            Message::PreSetValueUpdated(name, value) => {
+                self.environment_mut().insert(name, Rc::new(value));
+                self.environment_changed();
+                true
+            }
+            Message::PreSetValueRemoved(name) => {
+                self.environment_mut().remove(&name);
+                self.environment_changed();
+                true
+            }
+            Message::NewInput => {
+                let text_input = self.editor_text();
 
-                let result = glenside::language::interpreter::interpret_from_str::<f64>(
-                    &text_input,
-                    &self.environment,
-                );
-
-                let text_output = match result {
-                    glenside::language::interpreter::Value::Tensor(t) => {
-                        format!(
-                            "tensor with shape:\n\
-                             ({})\n\
-                             and value:\n\
-                             {:.2}",
-                            t.shape()
-                                .iter()
-                                .map(ToString::to_string)
-                                .collect::<Vec<_>>()
-                                .join(", "),
-                            t
-                        )
-                    }
-                    glenside::language::interpreter::Value::Access(a) => {
-                        format!(
-                            "access pattern with shape:\n(({a}), ({b}))\n\
-                             and value:\n\
-                             {tensor:.2}",
-                            a = a.tensor.shape()[..a.access_axis]
-                                .iter()
-                                .map(|i| i.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", "),
-                            b = a.tensor.shape()[a.access_axis..]
-                                .iter()
-                                .map(|i| i.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", "),
-                            tensor = a.tensor
-                        )
+                let result =
+                    evaluation::evaluate(&text_input, &interpreter_environment(&self.environment));
+
+                self.code_editor_link.with_editor(|editor| {
+                    editor_markers::set_error_markers(editor, &text_input, result.as_ref().err())
+                });
+
+                let tab = self.tab_mut();
+                tab.result_text = match &result {
+                    Ok(value) => result_format::format_value(value),
+                    Err(e) => e.to_string(),
+                };
+                tab.result_tensor = match result {
+                    Ok(Value::Tensor(t)) => Some((Rc::new(t), None)),
+                    Ok(Value::Access(a)) => Some((Rc::new(a.tensor), Some(a.access_axis))),
+                    _ => None,
+                };
+
+                true
+            }
+            Message::CheckShapes => {
+                let text_input = self.editor_text();
+
+                let result = shape_inference::check_shapes(&text_input, &self.environment_shapes());
+
+                self.code_editor_link.with_editor(|editor| {
+                    editor_markers::set_error_markers(editor, &text_input, result.as_ref().err())
+                });
+
+                let tab = self.tab_mut();
+                tab.result_tensor = None;
+                tab.result_text = match result {
+                    Ok(data) => format!(
+                        "program is well-shaped; result has shape:\n{}",
+                        shape_inference::describe(&data).unwrap_or_default()
+                    ),
+                    Err(e) => e.to_string(),
+                };
+
+                true
+            }
+            Message::Trace => {
+                let text_input = self.editor_text();
+
+                let result =
+                    evaluation::trace(&text_input, &interpreter_environment(&self.environment));
+
+                self.code_editor_link.with_editor(|editor| {
+                    editor_markers::set_error_markers(editor, &text_input, result.as_ref().err())
+                });
+
+                match result {
+                    Ok(trace) => {
+                        self.trace = Some(trace);
+                        self.trace_source = text_input;
+                        self.update(Message::TraceStep(0))
                     }
-                    glenside::language::interpreter::Value::Usize(_) => todo!(),
-                    glenside::language::interpreter::Value::Shape(_) => todo!(),
-                    glenside::language::interpreter::Value::ComputeType(_) => todo!(),
-                    glenside::language::interpreter::Value::PadType(_) => todo!(),
-                    glenside::language::interpreter::Value::AccessShape(shape, access_axis) => {
-                        format!(
-                            "access pattern shape literal with value:
-                             (({a}), ({b}))",
-                            a = shape.slice()[..access_axis]
-                                .iter()
-                                .map(|i| i.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", "),
-                            b = shape.slice()[access_axis..]
-                                .iter()
-                                .map(|i| i.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        )
+                    Err(e) => {
+                        self.trace = None;
+                        let tab = self.tab_mut();
+                        tab.result_text = e.to_string();
+                        tab.result_tensor = None;
+                        true
                     }
-                    glenside::language::interpreter::Value::List(_) => todo!(),
-                };
+                }
+            }
+            Message::TraceStep(step) => {
+                self.trace_step = step;
+
+                // Highlight the node whose value we're showing.
+                if let Some(trace) = &self.trace {
+                    let source = &self.trace_source;
+                    self.code_editor_link
+                        .with_editor(|editor| editor::select(editor, source, trace.spans[step]));
+                }
 
-                self.result_text = text_output;
+                true
+            }
+            Message::CursorMoved(offset) => {
+                let source = self.editor_text();
+                if source != self.program_tree_source {
+                    self.program_tree = Rc::new(
+                        program_tree::build_tree(&source, &self.environment_shapes())
+                            .unwrap_or_default(),
+                    );
+                    self.program_tree_source = source;
+                }
+
+                let spans = self
+                    .program_tree
+                    .iter()
+                    .map(|node| node.span)
+                    .collect::<Vec<_>>();
+                self.selected_node = spans::innermost_node(&spans, offset);
+
+                // Keep the debugger in sync with the selected node.
+                if let (Some(trace), Some(node)) = (&self.trace, self.selected_node) {
+                    if self.trace_source == self.program_tree_source && node < trace.values.len() {
+                        self.trace_step = node;
+                    }
+                }
 
                 true
             }
+            Message::NodeSelected(id) => {
+                let source = &self.program_tree_source;
+                let span = self.program_tree[id].span;
+                self.code_editor_link
+                    .with_editor(|editor| editor::select(editor, source, span));
+                false
+            }
             Message::ExampleSelected(None) => {
                 self.example_selected = None;
+                self.pre_set_environment = vec![];
 
                 // Restore previous environment
-                self.environment = self.user_environment_state.clone();
+                self.environment = Rc::new(self.user_environment_state.clone());
+                self.environment_changed();
+                self.save_workspace();
 
                 true
             }
             Message::ExampleSelected(Some(i)) => {
                 self.example_selected = Some(i);
 
-                // Save current input
-                self.user_editor_state = self
-                    .code_editor_link
-                    .with_editor(|editor| editor.get_model().unwrap().get_value())
-                    .unwrap();
+                // Open the example's program in its own tab, or go back to the
+                // tab it's already open in, so that no other program is lost.
+                match self
+                    .tabs
+                    .iter()
+                    .position(|tab| tab.name == EXAMPLES[i].name)
+                {
+                    Some(tab) if tab == self.current_tab => (),
+                    Some(tab) => self.switch_tab(tab),
+                    None => {
+                        self.tabs.push(Tab::new(
+                            EXAMPLES[i].name.to_string(),
+                            EXAMPLES[i].glenside_source.to_string(),
+                        ));
+                        self.switch_tab(self.tabs.len() - 1);
+                    }
+                }
 
                 // Take the environment from EXAMPLE[i]
-                self.environment = EXAMPLES[i].environment.clone();
+                self.pre_set_environment = EXAMPLES[i].environment_specs();
+                self.environment = Rc::new(EXAMPLES[i].environment(self.seed));
+                self.environment_changed();
+                self.save_workspace();
+
+                true
+            }
+            Message::DownloadResult => {
+                if let Some((tensor, _)) = &self.tab().result_tensor {
+                    download::download("result.npy", &npy::write_npy(tensor));
+                }
+                false
+            }
+            Message::DownloadEnvironment => {
+                let mut tensors = self
+                    .environment
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), &**value))
+                    .collect::<Vec<_>>();
+                if let Some((tensor, _)) = &self.tab().result_tensor {
+                    // Don't clobber a tensor in the environment called
+                    // "result".
+                    let name = if self.environment.contains_key("result") {
+                        "glenside_result"
+                    } else {
+                        "result"
+                    };
+                    tensors.push((name, tensor));
+                }
+                download::download("environment.npz", &npy::write_npz(tensors));
+                false
+            }
+            Message::EnvironmentSpecsUpdated(example_specs, user_specs) => {
+                self.example_environment_specs = example_specs;
+                self.user_environment_specs = user_specs;
+                self.save_workspace();
+                false
+            }
+            Message::ProgramEdited => {
+                self.save_workspace();
+                false
+            }
+            Message::TabSelected(i) => {
+                if i != self.current_tab {
+                    self.switch_tab(i);
+                    self.save_workspace();
+                }
+                true
+            }
+            Message::TabAdded => {
+                self.tabs
+                    .push(Tab::new(self.new_tab_name(), String::default()));
+                self.switch_tab(self.tabs.len() - 1);
+                self.save_workspace();
+                true
+            }
+            Message::TabClosed(i) => {
+                if self.tabs.len() == 1 {
+                    return false;
+                }
+                if i == self.current_tab {
+                    // Show a neighbouring tab before closing this one.
+                    let neighbour = if i + 1 < self.tabs.len() {
+                        i + 1
+                    } else {
+                        i - 1
+                    };
+                    self.switch_tab(neighbour);
+                }
+                self.tabs.remove(i);
+                if i < self.current_tab {
+                    self.current_tab -= 1;
+                }
+                self.save_workspace();
+                true
+            }
+            Message::CellModeToggled => {
+                self.cell_mode = !self.cell_mode;
+                true
+            }
+            Message::TabRenamed(name) => {
+                self.tab_mut().name = name;
+                self.save_workspace();
+                true
+            }
+            Message::ResetWorkspace => {
+                let window = web_sys::window().unwrap();
+                let confirmed = window
+                    .confirm_with_message(
+                        "Reset the workspace? Your program and environment will be lost.",
+                    )
+                    .unwrap_or(false);
+                if confirmed {
+                    Workspace::clear_saved();
+                    // Drop any permalink too, so that it isn't restored
+                    // instead.
+                    let location = window.location();
+                    location.set_hash("").unwrap();
+                    location.reload().unwrap();
+                }
+                false
+            }
+            Message::CreatePermalink => {
+                // The address bar is left alone, so that the permalink
+                // doesn't override the saved workspace when the page is
+                // reloaded.
+                let href = web_sys::window()
+                    .unwrap()
+                    .location()
+                    .href()
+                    .unwrap_or_default();
+                let (workspace, mut left_out) = self.workspace();
+                left_out.extend(workspace.values_left_out_of_fragment());
+                self.permalink = Some(format!(
+                    "{}#{}",
+                    href.split('#').next().unwrap(),
+                    workspace.to_fragment()
+                ));
+                self.permalink_left_out = left_out;
+                true
+            }
+            Message::SeedUpdated(seed) => {
+                self.seed = seed;
+                self.save_workspace();
 
+                // Each input, the example's included, regenerates its own value
+                // when it's given the new seed.
                 true
             }
         }
@@ -393,30 +996,148 @@ impl Component for App {
                  Glenside expression, and populates the environment with \
                  name-value pairs. Then, press \"interpret \
                  Glenside expression\" to evaluate the expression, and view \
-                 the result in the text box below."}</p>
+                 the result in the text box below. Tensor results are also \
+                 shown in a table, one slice at a time, and drawn as a \
+                 heatmap; hover over it to see individual values. Tensors in \
+                 the environment can be inspected in the same way, below the \
+                 environment inputs. To see just the shape of \
+                 the result, without running the program, press \"check \
+                 shapes\". To see the value of each sub-expression in the \
+                 order it is evaluated, press \"step through evaluation\". \
+                 Each of the editor's tabs holds its own program and result, \
+                 and all of them share the environment; examples open in a \
+                 tab of their own. The tree next to the editor shows the structure of the \
+                 program and the shape of each sub-expression; click a node \
+                 to find it in the editor."}</p>
             <p>{"All examples are editable, allowing you to write your own expressions. \
                  You can add new tensor variables into the environment using \
-                 the \"+\" button."}</p>
+                 the \"+\" button, or import them from NumPy .npy and .npz \
+                 files, CSV files (as 2D tensors) and JSON nested arrays; the \
+                 examples' tensors can be edited in the same way. Values can \
+                 also be pasted in, as CSV (e.g. from a spreadsheet), which \
+                 is reshaped to the shape given, or as a nested list. Random \
+                 tensors, including those in the examples, are generated \
+                 from the \"Seed:\" field, so the same seed always gives the \
+                 same values. Press \"permalink\" \
+                 to get a link to your program, environment and seed, to \
+                 share with others. Your workspace is saved in your browser \
+                 as you work, and restored when you come back; press \
+                 \"reset workspace\" to start afresh."}</p>
+            <p>{"In cell mode, programs are written as a notebook of \
+                 cells instead. Each cell can bind its result to a name, \
+                 putting it in the environment for later cells to use, so \
+                 that a program such as a network of several layers can be \
+                 built up one step at a time."}</p>
             </div>
             <br/>
             <div class={"row"}>
                 <div class={"column"}>
+                <label>
+                    <input type={"checkbox"} checked={self.cell_mode}
+                        onclick=self.link.callback(|_| Message::CellModeToggled) />
+                    {"cell mode"}
+                </label>
+                // Both modes stay mounted, so that neither loses its programs
+                // while the other is shown.
+                <div style={if self.cell_mode { "display: none" } else { "" }}>
+                { self.view_tabs() }
                 <CodeEditor
                     link=&self.code_editor_link
-                    options=Rc::new(get_options().with_value(
-                            self.example_selected.map(|i| EXAMPLES[i].glenside_source.to_string()).unwrap_or(self.user_editor_state.clone())))
+                    options=self.editor_options.clone()
                     />
                 <br/>
                 <input type={"button"} value={"interpret Glenside expression"} onclick=self.link.callback(|_| Message::NewInput) />
+                <input type={"button"} value={"check shapes"} onclick=self.link.callback(|_| Message::CheckShapes) />
                 <br/>
                 <br/>
                 <textarea
                     style={"width:500px; height:100px"}
                     readonly={true}>
-                    {self.result_text.clone()}</textarea>
+                    {self.tab().result_text.clone()}</textarea>
+                <br/>
+                <input type={"button"} value={"download result (.npy)"}
+                    disabled={self.tab().result_tensor.is_none()}
+                    onclick=self.link.callback(|_| Message::DownloadResult) />
+                <input type={"button"} value={"download environment and result (.npz)"}
+                    onclick=self.link.callback(|_| Message::DownloadEnvironment) />
+                {
+                    match &self.tab().result_tensor {
+                        Some((tensor, Some(access_axis))) => html! {
+                            <access_pattern_view::AccessPatternView
+                                tensor=tensor.clone()
+                                access_axis=*access_axis />
+                        },
+                        _ => html! {},
+                    }
+                }
+                {
+                    match &self.tab().result_tensor {
+                        Some((tensor, _)) => html! {
+                            <>
+                            <tensor_inspector::TensorInspector tensor=tensor.clone() />
+                            <heatmap::Heatmap tensor=tensor.clone() />
+                            </>
+                        },
+                        None => html! {},
+                    }
+                }
+                <br/>
+                <input type={"button"} value={"step through evaluation"} onclick=self.link.callback(|_| Message::Trace) />
+                { self.view_trace() }
+                </div>
+                <div style={if self.cell_mode { "" } else { "display: none" }}>
+                <notebook::Notebook
+                    environment=self.environment.clone()
+                    value_updated_callback=self.link.callback(|(name, value)| {
+                        Message::BindingUpdated(name, value)
+                    })
+                    value_removed_callback=self.link.callback(Message::BindingRemoved) />
+                </div>
+                </div>
+                <div class={"column"}>
+                <program_tree::ProgramTree
+                    nodes=self.program_tree.clone()
+                    selected=self.selected_node
+                    on_select=self.link.callback(Message::NodeSelected) />
                 </div>
                 <div class={"column"}>
-                <ExampleChooser example_chosen_callback=self.link.callback(|i| Message::ExampleSelected(i)) />
+                <ExampleChooser
+                    example_chosen_callback=self.link.callback(|i| Message::ExampleSelected(i))
+                    selected={self.example_selected} />
+                <label for={"seed"}>{"Seed: "}</label>
+                <input name={"seed"} type={"number"} min={"0"} value={self.seed.to_string()}
+                    oninput=self.link.batch_callback(|event: InputData| {
+                        event.value.parse().ok().map(Message::SeedUpdated).into_iter().collect()
+                    }) />
+                <br/>
+                <input type={"button"} value={"permalink"}
+                    onclick=self.link.callback(|_| Message::CreatePermalink) />
+                <input type={"button"} value={"reset workspace"}
+                    onclick=self.link.callback(|_| Message::ResetWorkspace) />
+                {
+                    match &self.permalink {
+                        Some(permalink) => html! {
+                            <>
+                            <input type={"text"} readonly={true} value={permalink} />
+                            {
+                                if self.permalink_left_out.is_empty() {
+                                    html! {}
+                                } else {
+                                    html! {
+                                        <div>
+                                            {format!(
+                                                "Too large to include: {}",
+                                                self.permalink_left_out.join(", ")
+                                            )}
+                                        </div>
+                                    }
+                                }
+                            }
+                            </>
+                        },
+                        None => html! {},
+                    }
+                }
                 <br/>
                 <div class="example-text">
                   { self.example_selected.map(|i| EXAMPLES[i].description).unwrap_or_default() }
@@ -426,7 +1147,20 @@ impl Component for App {
                     value_updated_callback=self.link.callback(|(name, value)| {
                         Message::EnvironmentValueUpdated(name, value)
                     })
-                    pre_set_environment={self.example_selected.map(|i| EXAMPLES[i].environment.clone())} />
+                    value_removed_callback=self.link.callback(Message::EnvironmentValueRemoved)
+                    pre_set_value_updated_callback=self.link.callback(|(name, value)| {
+                        Message::PreSetValueUpdated(name, value)
+                    })
+                    pre_set_value_removed_callback=self.link.callback(Message::PreSetValueRemoved)
+                    specs_updated_callback=self.link.callback(|(example_specs, user_specs)| {
+                        Message::EnvironmentSpecsUpdated(example_specs, user_specs)
+                    })
+                    seed={self.seed}
+                    initial_environment={self.user_environment_specs.clone()}
+                    pre_set_environment={self.pre_set_environment.clone()}
+                    pre_set_environment_id={self.example_selected} />
+                <br/>
+                { self.view_environment() }
                 </div>
             </div>
             </>
@@ -437,6 +1171,9 @@ impl Component for App {
 #[derive(Properties, Clone)]
 struct ExampleChooserProperties {
     example_chosen_callback: yew::Callback<Option<usize>>,
+    /// The index into [`EXAMPLES`] of the example selected to begin with.
+    #[prop_or_default]
+    selected: Option<usize>,
 }
 struct ExampleChooser {
     link: ComponentLink<Self>,
@@ -456,8 +1193,8 @@ impl Component for ExampleChooser {
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         Self {
             link,
+            selected_example_index: props.selected,
             properties: props,
-            selected_example_index: None,
         }
     }
 
@@ -524,33 +1261,130 @@ impl Component for ExampleChooser {
     }
 }
 
+/// One of the inputs shown by [`EnvironmentInputs`].
+struct EnvironmentInputRow {
+    id: usize,
+    /// Whether the input belongs to the pre-set environment, and so is
+    /// replaced along with it.
+    pre_set: bool,
+    /// The input's spec: the one it started off as, if any, until it reports
+    /// a new one.
+    spec: Option<TensorSpec>,
+}
+
 struct EnvironmentInputs {
     props: EnvironmentInputsProps,
     link: ComponentLink<Self>,
-    num_environment_inputs: usize,
+    /// The inputs currently shown.
+    environment_inputs: Vec<EnvironmentInputRow>,
+    /// The id to give the next input added.
+    next_input_id: usize,
+    reader: ReaderService,
+    /// The files being read for import.
+    reading: Vec<ReaderTask>,
+    /// The names and shapes of the tensors imported from files.
+    imported: Vec<(String, Vec<usize>)>,
+    /// Why the last import failed, if it did.
+    import_error: Option<String>,
 }
 
 #[derive(Properties, Clone)]
 struct EnvironmentInputsProps {
     /// The callback to the parent, which should be called when any of the
-    /// environment inputs change. This is the *only* way in which this
+    /// user's environment inputs change. Along with [`value_removed_callback`]
+    /// and their pre-set counterparts, this is the *only* way in which this
     /// component communicates anything about the environment. Messages about
-    /// the environment are in the form of (name, tensor) pairs, saying that the
-    /// tensor with `name` now has value `tensor`. Thus, there is currently no
-    /// way to remove tensors from the environment.
+    /// the environment are in the form of (name, tensor) pairs, saying that
+    /// the tensor with `name` now has value `tensor`.
     value_updated_callback: yew::Callback<(String, ArrayD<f64>)>,
-    /// A pre-set environment. Currently, the component is just given this for
-    /// display purposes. That is, [`value_updated_callback`] is not called for
-    /// the tensors in the pre-set environment. The creator of this component is
-    /// assumed to put these tensors in their environment manually. In the
-    /// future, we could make it so that [`value_updated_callback`] is called
-    /// right away for each of the tensors in the pre-set environment.
+    /// The callback to the parent, which should be called with the name of a
+    /// tensor when it should be removed from the environment: when its input
+    /// is deleted, or renamed.
+    value_removed_callback: yew::Callback<String>,
+    /// Like [`value_updated_callback`], but called for the pre-set inputs
+    /// instead, as their tensors belong to the pre-set environment rather
+    /// than the user's.
+    pre_set_value_updated_callback: yew::Callback<(String, ArrayD<f64>)>,
+    /// Like [`value_removed_callback`], but called for the pre-set inputs.
+    pre_set_value_removed_callback: yew::Callback<String>,
+    /// Called with the specs of the pre-set inputs and of the other inputs,
+    /// respectively, whenever any of them change.
+    specs_updated_callback: yew::Callback<(Vec<TensorSpec>, Vec<TensorSpec>)>,
+    /// The specs of the inputs to start off with, below the pre-set ones. Only
+    /// read when this component is created. As with the pre-set environment,
+    /// the creator of this component is assumed to put their values in their
+    /// environment manually.
+    #[prop_or_default]
+    initial_environment: Vec<TensorSpec>,
+    /// The specs of the tensors in a pre-set environment, such as an
+    /// example's. Each gets an input which starts off with its spec.
+    /// [`pre_set_value_updated_callback`] is only called for these tensors
+    /// once they're edited, or regenerated from a new seed; the creator of
+    /// this component is assumed to put their initial values in their
+    /// environment manually.
+    #[prop_or_default]
+    pre_set_environment: Vec<TensorSpec>,
+    /// Identifies the pre-set environment. When it changes, the pre-set
+    /// inputs are replaced, even if the names and shapes are the same.
     #[prop_or_default]
-    pre_set_environment: Option<Environment<'static, f64>>,
+    pre_set_environment_id: Option<usize>,
+    /// The global seed, from which random tensors are generated.
+    seed: u64,
 }
 
 enum EnvironmentInputsMessage {
     Add,
+    /// Remove the input with the given id.
+    Remove(usize),
+    /// The user chose files to import tensors from.
+    FilesChosen(Vec<File>),
+    FileLoaded(FileData),
+    /// Remove the imported tensor with the given name.
+    RemoveImported(String),
+    /// The input with the given id has a new spec.
+    SpecUpdated(usize, TensorSpec),
+}
+
+impl EnvironmentInputs {
+    /// Makes a new input, with a new id so that it starts afresh.
+    fn new_input(&mut self, spec: Option<TensorSpec>, pre_set: bool) -> EnvironmentInputRow {
+        self.next_input_id += 1;
+        EnvironmentInputRow {
+            id: self.next_input_id - 1,
+            pre_set,
+            spec,
+        }
+    }
+
+    /// Adds an input for each tensor in the pre-set environment, before any
+    /// other inputs.
+    fn add_pre_set_inputs(&mut self) {
+        let pre_set_inputs = self
+            .props
+            .pre_set_environment
+            .clone()
+            .into_iter()
+            .map(|spec| self.new_input(Some(spec), true))
+            .collect::<Vec<_>>();
+        self.environment_inputs.splice(0..0, pre_set_inputs);
+    }
+
+    /// Tells the parent the specs of all of the inputs.
+    fn emit_specs(&self) {
+        let (pre_set, other): (Vec<_>, Vec<_>) = self
+            .environment_inputs
+            .iter()
+            .partition(|input| input.pre_set);
+        let specs = |inputs: Vec<&EnvironmentInputRow>| {
+            inputs
+                .into_iter()
+                .filter_map(|input| input.spec.clone())
+                .collect()
+        };
+        self.props
+            .specs_updated_callback
+            .emit((specs(pre_set), specs(other)));
+    }
 }
 
 impl Component for EnvironmentInputs {
@@ -558,24 +1392,94 @@ impl Component for EnvironmentInputs {
     type Properties = EnvironmentInputsProps;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Self {
+        let mut environment_inputs = Self {
             props,
             link,
-            num_environment_inputs: 0,
+            environment_inputs: vec![],
+            next_input_id: 0,
+            reader: ReaderService::new(),
+            reading: vec![],
+            imported: vec![],
+            import_error: None,
+        };
+        environment_inputs.add_pre_set_inputs();
+        for spec in environment_inputs.props.initial_environment.clone() {
+            let input = environment_inputs.new_input(Some(spec), false);
+            environment_inputs.environment_inputs.push(input);
         }
+        environment_inputs
     }
 
     fn update(&mut self, msg: EnvironmentInputsMessage) -> ShouldRender {
         match msg {
             EnvironmentInputsMessage::Add => {
-                self.num_environment_inputs += 1;
+                let input = self.new_input(None, false);
+                self.environment_inputs.push(input);
+                true
+            }
+            EnvironmentInputsMessage::Remove(id) => {
+                self.environment_inputs.retain(|input| input.id != id);
+                self.emit_specs();
                 true
             }
+            EnvironmentInputsMessage::FilesChosen(files) => {
+                for file in files {
+                    match self.reader.read_file(
+                        file,
+                        self.link.callback(EnvironmentInputsMessage::FileLoaded),
+                    ) {
+                        Ok(task) => self.reading.push(task),
+                        Err(e) => self.import_error = Some(e.to_string()),
+                    }
+                }
+                true
+            }
+            EnvironmentInputsMessage::FileLoaded(file) => {
+                self.reading.retain(|task| task.is_active());
+                match import::parse_file(&file.name, &file.content) {
+                    Ok(tensors) => {
+                        self.import_error = None;
+                        for (name, value) in tensors {
+                            self.imported.retain(|(n, _)| *n != name);
+                            self.imported.push((name.clone(), value.shape().to_vec()));
+                            self.props.value_updated_callback.emit((name, value));
+                        }
+                    }
+                    Err(e) => self.import_error = Some(e),
+                }
+                true
+            }
+            EnvironmentInputsMessage::RemoveImported(name) => {
+                self.imported.retain(|(n, _)| *n != name);
+                self.props.value_removed_callback.emit(name);
+                true
+            }
+            EnvironmentInputsMessage::SpecUpdated(id, spec) => {
+                if let Some(input) = self
+                    .environment_inputs
+                    .iter_mut()
+                    .find(|input| input.id == id)
+                {
+                    input.spec = Some(spec);
+                }
+                self.emit_specs();
+                // The input shows its own spec.
+                false
+            }
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let pre_set_changed = props.pre_set_environment_id != self.props.pre_set_environment_id
+            || props.pre_set_environment != self.props.pre_set_environment;
         self.props = props;
+
+        if pre_set_changed {
+            self.environment_inputs.retain(|input| !input.pre_set);
+            self.add_pre_set_inputs();
+            self.emit_specs();
+        }
+
         true
     }
 
@@ -585,22 +1489,54 @@ impl Component for EnvironmentInputs {
                 // Button to instantiate inputs
                 <input type={"button"} value={"+"} onclick=self.link.callback(|_| EnvironmentInputsMessage::Add) />
 
-                // Pre-set inputs
+                // Importing from files
+                <label for={"import"}>{" Import .npy/.npz/.csv/.json: "}</label>
+                <input name={"import"} type={"file"} accept={".npy,.npz,.csv,.json"} multiple={true}
+                    onchange=self.link.callback(|event: ChangeData| match event {
+                        ChangeData::Files(files) => EnvironmentInputsMessage::FilesChosen(
+                            (0..files.length()).filter_map(|i| files.get(i)).collect(),
+                        ),
+                        _ => unreachable!(),
+                    }) />
+                <div>{self.import_error.clone().unwrap_or_default()}</div>
                 {
-                    for self.props.pre_set_environment.as_ref().map(|e| e.iter().map(|(name, value)| {
-                        html_nested!{
-                            <PreSetInput
-                                name=name.clone()
-                                value=value.clone() />
+                    for self.imported.iter().map(|(name, shape)| {
+                        let name = name.clone();
+                        html! {
+                            <div>
+                                <code>{&name}</code>
+                                {format!(" ({}), imported ", result_format::format_dims(shape))}
+                                <input type={"button"} value={"delete"}
+                                    onclick=self.link.callback(move |_| {
+                                        EnvironmentInputsMessage::RemoveImported(name.clone())
+                                    }) />
+                            </div>
                         }
-                    }).collect::<Vec<_>>()).unwrap_or_default()
+                    })
                 }
+
                 {
-                    for (0..self.num_environment_inputs).map(|i| {
+                    for self.environment_inputs.iter().map(|input| {
                         html_nested!{
                             <GeneratedTensorEnvironmentInput
-                                id={i}
-                                value_updated_callback=self.props.value_updated_callback.clone() />
+                                key={input.id}
+                                id={input.id}
+                                initial_spec={input.spec.clone()}
+                                seed={self.props.seed}
+                                value_updated_callback={if input.pre_set {
+                                    self.props.pre_set_value_updated_callback.clone()
+                                } else {
+                                    self.props.value_updated_callback.clone()
+                                }}
+                                value_removed_callback={if input.pre_set {
+                                    self.props.pre_set_value_removed_callback.clone()
+                                } else {
+                                    self.props.value_removed_callback.clone()
+                                }}
+                                spec_updated_callback=self.link.callback(|(id, spec)| {
+                                    EnvironmentInputsMessage::SpecUpdated(id, spec)
+                                })
+                                delete_callback=self.link.callback(EnvironmentInputsMessage::Remove) />
                         }
                     })
                 }
@@ -609,126 +1545,174 @@ impl Component for EnvironmentInputs {
     }
 }
 
-struct PreSetInput(PreSetInputProperties);
-
-#[derive(Properties, Clone)]
-struct PreSetInputProperties {
-    name: String,
-    value: ArrayD<f64>,
-}
-
-impl Component for PreSetInput {
-    type Message = ();
-    type Properties = PreSetInputProperties;
-
-    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
-        Self(props)
-    }
-
-    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
-        unreachable!()
-    }
-
-    fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        self.0 = props;
-        true
-    }
-
-    fn view(&self) -> Html {
-        html! {
-            <div>
-                <label for={"name"}>{"Name"}</label>
-                <input name={"name"} type={"text"} value={ &self.0.name }
-                    disabled={true} />
-
-                // Shape text box
-                <label for={"shape"}>{"Shape"}</label>
-                <input name={"shape"} type={"text"}
-                  value={ format!("({})", self.0.value.shape().iter().map(std::string::ToString::to_string).collect::<Vec<_>>().join(",")) }
-                  disabled={true}/>
-            </div>
-        }
-    }
-}
-
 #[derive(Properties, Clone)]
 struct EnvironmentInputProps {
     value_updated_callback: yew::Callback<(String, ArrayD<f64>)>,
-    /// Unique id identifying this input in a list of inputs. Currently only
-    /// used so that we can make the names of the radio button groups unique.
+    value_removed_callback: yew::Callback<String>,
+    /// Called with [`EnvironmentInputProps::id`] when the user deletes this
+    /// input.
+    delete_callback: yew::Callback<usize>,
+    /// Unique id identifying this input in a list of inputs. Tells the parent
+    /// which input [`EnvironmentInputProps::delete_callback`] and
+    /// [`EnvironmentInputProps::spec_updated_callback`] are about, and makes
+    /// the names of the radio button groups unique.
     id: usize,
+    /// The global seed. The seed for this input's random values is derived
+    /// from it and the input's name.
+    seed: u64,
+    /// Called with [`EnvironmentInputProps::id`] and the input's spec
+    /// whenever the spec changes.
+    spec_updated_callback: yew::Callback<(usize, TensorSpec)>,
+    /// The spec this input starts off with, if any. Its value is assumed to
+    /// already be in the parent's environment.
+    #[prop_or_default]
+    initial_spec: Option<TensorSpec>,
 }
 
 struct GeneratedTensorEnvironmentInput {
     properties: EnvironmentInputProps,
     link: ComponentLink<Self>,
-    name: String,
-    shape_string: String,
-    value_generation_strategy: Option<ValueGenerationStrategy>,
+    spec: TensorSpec,
+    /// The name under which this input's value was last sent to the parent,
+    /// if any. This is the name which needs removing from the environment
+    /// when the input is renamed or deleted.
+    emitted_name: Option<String>,
 }
 
 impl GeneratedTensorEnvironmentInput {
-    fn get_value(&self) -> Option<(String, ArrayD<f64>)> {
-        // First and last characters should be parens.
-        if self.shape_string.is_empty()
-            || self.shape_string.chars().nth(0).unwrap() != '('
-            || self
-                .shape_string
-                .chars()
-                .nth(self.shape_string.len() - 1)
-                .unwrap()
-                != ')'
-        {
-            return None;
+    /// Sends the current value to the parent, if it's valid.
+    fn emit_value(&mut self) {
+        if let Some(value) = self.get_value() {
+            self.emitted_name = Some(value.0.clone());
+            self.properties.value_updated_callback.emit(value);
         }
+    }
 
-        let parens_trimmed = &self.shape_string[1..self.shape_string.len() - 1];
+    /// Tells the parent to remove the value last sent, if any.
+    fn remove_emitted_value(&mut self) {
+        if let Some(name) = self.emitted_name.take() {
+            self.properties.value_removed_callback.emit(name);
+        }
+    }
 
-        let parse_results = if parens_trimmed.is_empty() {
-            vec![]
-        } else {
-            parens_trimmed
-                .split(",")
-                .map(|s| s.parse::<usize>())
-                .collect::<Vec<_>>()
-        };
+    fn get_value(&self) -> Option<(String, ArrayD<f64>)> {
+        self.spec
+            .value(self.properties.seed)
+            .map(|value| (self.spec.name.clone(), value))
+    }
 
-        if !parse_results.is_empty() && parse_results.iter().any(|r| r.is_err()) {
-            return None;
+    /// A radio button choosing `strategy`. Choosing a strategy with
+    /// parameters starts it off with the parameters given here.
+    fn view_strategy_radio(
+        &self,
+        id: &str,
+        label: &str,
+        strategy: ValueGenerationStrategy,
+    ) -> Html {
+        let id = format!("{}-{}", id, self.properties.id);
+        let checked = self
+            .spec
+            .strategy
+            .map(|s| std::mem::discriminant(&s) == std::mem::discriminant(&strategy))
+            .unwrap_or(false);
+        html! {
+            <>
+            <input type={"radio"}
+                id={&id}
+                name={format!("values-{}", self.properties.id)}
+                checked={checked}
+                oninput=self.link.callback(move |_|
+                    GeneratedTensorEnvironmentInputMessage::UpdateValueGenerationStrategy(strategy))
+            />
+            <label for={&id}>{label}</label>
+            </>
         }
+    }
 
-        let shape = parse_results
-            .iter()
-            .map(|r| *r.as_ref().unwrap())
-            .collect::<Vec<_>>();
+    /// A text box for one of the current strategy's parameters. `update`
+    /// builds the strategy with the new value of the parameter.
+    fn view_parameter<T: std::str::FromStr + ToString + 'static>(
+        &self,
+        label: &str,
+        value: T,
+        update: impl Fn(T) -> ValueGenerationStrategy + 'static,
+    ) -> Html {
+        html! {
+            <label>
+                {format!(" {} ", label)}
+                <input type={"number"} style={"width: 5em"} value={value.to_string()}
+                    oninput=self.link.batch_callback(move |event: InputData| {
+                        // Keep the last valid value until the input parses.
+                        event
+                            .value
+                            .parse()
+                            .ok()
+                            .map(|value| {
+                                GeneratedTensorEnvironmentInputMessage::UpdateValueGenerationStrategy(
+                                    update(value),
+                                )
+                            })
+                            .into_iter()
+                            .collect()
+                    }) />
+            </label>
+        }
+    }
 
-        match self.value_generation_strategy {
-            Some(ValueGenerationStrategy::Zeros) => {
-                Some((self.name.clone(), ndarray::ArrayD::zeros(shape)))
-            }
-            Some(ValueGenerationStrategy::Ones) => {
-                Some((self.name.clone(), ndarray::ArrayD::ones(shape)))
-            }
-            Some(ValueGenerationStrategy::Random) => Some((
-                self.name.clone(),
-                ndarray::ArrayD::from_shape_fn(shape, |_| {
-                    Uniform::new(-2.0, 2.0).sample(&mut OsRng::new().unwrap())
-                }),
-            )),
-            None => None,
+    /// The inputs for the current strategy's parameters, if it has any.
+    fn view_strategy_parameters(&self) -> Html {
+        match self.spec.strategy {
+            Some(strategy @ ValueGenerationStrategy::Explicit)
+            | Some(strategy @ ValueGenerationStrategy::Csv) => html! {
+                <div>
+                    <textarea
+                        placeholder={if strategy == ValueGenerationStrategy::Csv {
+                            "e.g. 1,2,3\n4,5,6, or paste from a spreadsheet"
+                        } else {
+                            "e.g. [[1, 2, 3], [4, 5, 6]]"
+                        }}
+                        value={&self.spec.values_string}
+                        oninput=self.link.callback(|event: InputData| {
+                            GeneratedTensorEnvironmentInputMessage::UpdateValuesString(event.value)
+                        }) />
+                    <br/>
+                    {self.spec.explicit_value().err().unwrap_or_default()}
+                </div>
+            },
+            Some(ValueGenerationStrategy::Constant(c)) => html! {
+                <div>
+                    {self.view_parameter("value", c, ValueGenerationStrategy::Constant)}
+                </div>
+            },
+            Some(ValueGenerationStrategy::Normal {
+                mean,
+                std_dev,
+                seed,
+            }) => html! {
+                <div>
+                    {self.view_parameter("mean", mean, move |mean| ValueGenerationStrategy::Normal { mean, std_dev, seed })}
+                    {self.view_parameter("std. dev.", std_dev, move |std_dev| ValueGenerationStrategy::Normal { mean, std_dev, seed })}
+                    {self.view_parameter("seed", seed, move |seed| ValueGenerationStrategy::Normal { mean, std_dev, seed })}
+                </div>
+            },
+            Some(ValueGenerationStrategy::Uniform { low, high, seed }) => html! {
+                <div>
+                    {self.view_parameter("low", low, move |low| ValueGenerationStrategy::Uniform { low, high, seed })}
+                    {self.view_parameter("high", high, move |high| ValueGenerationStrategy::Uniform { low, high, seed })}
+                    {self.view_parameter("seed", seed, move |seed| ValueGenerationStrategy::Uniform { low, high, seed })}
+                </div>
+            },
+            _ => html! {},
         }
     }
 }
-enum ValueGenerationStrategy {
-    Random,
-    Zeros,
-    Ones,
-}
 
 enum GeneratedTensorEnvironmentInputMessage {
     UpdateName(String),
     UpdateShapeString(String),
     UpdateValueGenerationStrategy(ValueGenerationStrategy),
+    UpdateValuesString(String),
+    Delete,
 }
 
 impl Component for GeneratedTensorEnvironmentInput {
@@ -736,33 +1720,56 @@ impl Component for GeneratedTensorEnvironmentInput {
     type Properties = EnvironmentInputProps;
 
     fn create(properties: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let spec = properties.initial_spec.clone();
         Self {
             properties,
             link,
-            name: String::default(),
-            shape_string: String::default(),
-            value_generation_strategy: None,
+            // The parent already has this tensor, so it needs removing if the
+            // input is renamed or deleted.
+            emitted_name: spec.as_ref().map(|spec| spec.name.clone()),
+            spec: spec.unwrap_or_default(),
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            GeneratedTensorEnvironmentInputMessage::UpdateName(s) => self.name = s,
-            GeneratedTensorEnvironmentInputMessage::UpdateShapeString(s) => self.shape_string = s,
+            GeneratedTensorEnvironmentInputMessage::UpdateName(s) => {
+                self.remove_emitted_value();
+                self.spec.name = s
+            }
+            GeneratedTensorEnvironmentInputMessage::UpdateShapeString(s) => {
+                self.spec.shape_string = s
+            }
             GeneratedTensorEnvironmentInputMessage::UpdateValueGenerationStrategy(s) => {
-                self.value_generation_strategy = Some(s)
+                self.spec.strategy = Some(s)
+            }
+            GeneratedTensorEnvironmentInputMessage::UpdateValuesString(s) => {
+                self.spec.values_string = s
+            }
+            GeneratedTensorEnvironmentInputMessage::Delete => {
+                self.remove_emitted_value();
+                self.properties.delete_callback.emit(self.properties.id);
+                return false;
             }
         }
 
-        if let Some(value) = self.get_value() {
-            self.properties.value_updated_callback.emit(value);
-        }
+        self.emit_value();
+        self.properties
+            .spec_updated_callback
+            .emit((self.properties.id, self.spec.clone()));
 
         true
     }
 
     fn change(&mut self, properties: Self::Properties) -> ShouldRender {
+        let seed_changed = properties.seed != self.properties.seed;
         self.properties = properties;
+
+        // Regenerate random values from the new seed.
+        if seed_changed {
+            self.emit_value();
+        }
+
         true
     }
 
@@ -771,59 +1778,37 @@ impl Component for GeneratedTensorEnvironmentInput {
             <div>
                 // Name text box
                 <label for={"name"}>{"Name"}</label>
-                <input name={"name"} type={"text"} oninput=self.link.callback(
+                <input name={"name"} type={"text"} value={&self.spec.name} oninput=self.link.callback(
                     |event: InputData| GeneratedTensorEnvironmentInputMessage::UpdateName(event.value)) />
 
                 // Shape text box
                 <label for={"shape"}>{"Shape"}</label>
                 <input name={"shape"} type={"text"} placeholder={"e.g. () or (3,32,32)"}
+                    value={&self.spec.shape_string}
                     oninput=self.link.callback(|event: InputData| {
                         GeneratedTensorEnvironmentInputMessage::UpdateShapeString(event.value)
                     })
                 />
 
                 // Value generation radio buttons
-                <input type={"radio"}
-                    id={format!("random-{}", self.properties.id)}
-                    name={format!("values-{}", self.properties.id)}
-                    checked={match self.value_generation_strategy {
-                        Some(ValueGenerationStrategy::Random) => true,
-                        _ => false,
-                    }}
-                    oninput=self.link.callback(|_|
-                        GeneratedTensorEnvironmentInputMessage::UpdateValueGenerationStrategy(
-                            ValueGenerationStrategy::Random
-                        ))
-                />
-                <label for={format!("random-{}", self.properties.id)}>{"random"}</label>
-
-                <input type={"radio"}
-                    id={format!("zeros-{}", self.properties.id)}
-                    name={format!("values-{}", self.properties.id)}
-                    checked={match self.value_generation_strategy {
-                        Some(ValueGenerationStrategy::Zeros) => true,
-                        _ => false,
-                    }}
-                    oninput=self.link.callback(|_|
-                        GeneratedTensorEnvironmentInputMessage::UpdateValueGenerationStrategy(
-                            ValueGenerationStrategy::Zeros
-                        ))
-                />
-                <label for={format!("zeros-{}", self.properties.id)}>{"zeros"}</label>
-
-                <input type={"radio"}
-                    id={format!("ones-{}", self.properties.id)}
-                    name={format!("values-{}", self.properties.id)}
-                    checked={match self.value_generation_strategy {
-                        Some(ValueGenerationStrategy::Ones) => true,
-                        _ => false,
-                    }}
-                    oninput=self.link.callback(|_|
-                        GeneratedTensorEnvironmentInputMessage::UpdateValueGenerationStrategy(
-                            ValueGenerationStrategy::Ones
-                        ))
-                />
-                <label for={format!("ones-{}", self.properties.id)}>{"ones"}</label>
+                {self.view_strategy_radio("random", "random", ValueGenerationStrategy::Random)}
+                {self.view_strategy_radio("zeros", "zeros", ValueGenerationStrategy::Zeros)}
+                {self.view_strategy_radio("ones", "ones", ValueGenerationStrategy::Ones)}
+                {self.view_strategy_radio("arange", "arange", ValueGenerationStrategy::Arange)}
+                {self.view_strategy_radio("identity", "identity", ValueGenerationStrategy::Identity)}
+                {self.view_strategy_radio("constant", "constant", ValueGenerationStrategy::Constant(0.0))}
+                {self.view_strategy_radio("normal", "normal", ValueGenerationStrategy::Normal {
+                    mean: 0.0,
+                    std_dev: 1.0,
+                    seed: 0,
+                })}
+                {self.view_strategy_radio("uniform", "uniform", ValueGenerationStrategy::Uniform {
+                    low: -2.0,
+                    high: 2.0,
+                    seed: 0,
+                })}
+                {self.view_strategy_radio("explicit", "explicit values", ValueGenerationStrategy::Explicit)}
+                {self.view_strategy_radio("csv", "CSV", ValueGenerationStrategy::Csv)}
 
                 <input
                     type={"checkbox"}
@@ -832,6 +1817,12 @@ impl Component for GeneratedTensorEnvironmentInput {
                     checked={match self.get_value() { Some(_) => true, _ => false}}
                 />
                 <label for={"valid"}>{"valid?"}</label>
+
+                <input type={"button"} value={"delete"}
+                    onclick=self.link.callback(|_| GeneratedTensorEnvironmentInputMessage::Delete) />
+
+                // Parameters of the value generation strategy
+                {self.view_strategy_parameters()}
             </div>
         }
     }
@@ -840,7 +1831,36 @@ impl Component for GeneratedTensorEnvironmentInput {
 #[wasm_bindgen(start)]
 pub fn start_app() {
     wasm_logger::init(wasm_logger::Config::default());
-    yew::start_app::<App>();
+    glenside_language::register();
+
+    // Restore the workspace from a permalink, if there is one. It takes the
+    // place of the saved workspace once the app has been built from it; see
+    // `App::rendered`.
+    let window = web_sys::window().unwrap();
+    let location = window.location();
+    let fragment = location.hash().unwrap_or_default();
+    let workspace = if fragment.is_empty() {
+        None
+    } else {
+        let workspace = Workspace::from_fragment(&fragment)
+            .map_err(|e| log::warn!("{}", e))
+            .ok();
+        // The fragment has served its purpose; leave the address bar as
+        // plain as it was before the permalink was followed.
+        let url = format!(
+            "{}{}",
+            location.pathname().unwrap_or_default(),
+            location.search().unwrap_or_default()
+        );
+        if let Ok(history) = window.history() {
+            history
+                .replace_state_with_url(&JsValue::NULL, "", Some(&url))
+                .ok();
+        }
+        workspace
+    };
+
+    yew::start_app_with_props::<App>(AppProps { workspace });
 }
 
 #[cfg(test)]
@@ -849,10 +1869,47 @@ mod tests {
     #[test]
     fn run_all_examples() {
         for example in EXAMPLES.iter() {
-            glenside::language::interpreter::interpret_from_str::<f64>(
+            // Through the shape check too, which mustn't refuse any example.
+            let result = evaluation::evaluate(
                 example.glenside_source,
-                &example.environment,
+                &interpreter_environment(&example.environment(0)),
+            );
+            assert!(result.is_ok(), "{}: {:?}", example.name, result.err());
+        }
+    }
+
+    #[test]
+    fn example_results_are_pinned() {
+        // Permalinks hold seeds rather than values, so an example's result at
+        // a given seed must never change.
+        let example = &COMPUTING_OVER_ACCESS_PATTERNS_0;
+        let result = match glenside::language::interpreter::interpret_from_str::<f64>(
+            example.glenside_source,
+            &interpreter_environment(&example.environment(1)),
+        ) {
+            Value::Access(a) => a.tensor,
+            Value::Tensor(t) => t,
+            _ => panic!(),
+        };
+        assert_eq!(result.shape(), &[2]);
+        // The sums of t's rows, which are [0.6162963121534668,
+        // 1.356415910341024, 1.006839442716755] and [0.5384219369594305,
+        // -1.343221073385017, -0.26356959933347124].
+        for (value, expected) in result.iter().zip(&[2.979551665211246, -1.0683687357590577]) {
+            assert!(
+                (value - expected).abs() < 1e-12,
+                "{} != {}",
+                value,
+                expected
             );
         }
     }
+
+    #[test]
+    fn example_environments_are_deterministic() {
+        for example in EXAMPLES.iter() {
+            assert_eq!(example.environment(1), example.environment(1));
+            assert_ne!(example.environment(1), example.environment(2));
+        }
+    }
 }
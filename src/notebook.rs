@@ -0,0 +1,411 @@
+//! A notebook of Glenside expressions, evaluated one cell at a time. A cell
+//! can give its result a name, binding it into the environment so that later
+//! cells can use it. Large programs, such as a network of several layers, can
+//! then be built up step by step rather than as one deeply nested expression.
+
+use crate::operators::{self, COMPUTE_TYPES, PAD_TYPES};
+use crate::{
+    evaluation, interpreter_environment, result_format, tensor_inspector, TensorEnvironment,
+};
+use egg::RecExpr;
+use glenside::language::interpreter::{Access, Value};
+use glenside::language::Language;
+use ndarray::ArrayD;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::str::FromStr;
+use yew::{html, Component, ComponentLink, Html, InputData, Properties, ShouldRender};
+
+/// Checks that `name` can be used to refer to a cell's result from a Glenside
+/// program.
+pub fn check_target(name: &str) -> Result<(), String> {
+    // Glenside reads some names as something other than a variable, e.g.
+    // compute types.
+    let is_variable = match RecExpr::<Language>::from_str(name) {
+        Ok(expr) => matches!(expr.as_ref(), [Language::Symbol(_)]),
+        Err(_) => false,
+    };
+    if name
+        .chars()
+        .any(|c| c.is_whitespace() || c == '(' || c == ')')
+    {
+        Err(format!(
+            "'{}' can't be a name: names can't contain spaces or parentheses",
+            name
+        ))
+    } else if name.parse::<f64>().is_ok() {
+        Err(format!("'{}' can't be a name: it's a number", name))
+    } else if operators::find_operator(name).is_some()
+        || COMPUTE_TYPES.contains(&name)
+        || PAD_TYPES.contains(&name)
+        || !is_variable
+    {
+        Err(format!(
+            "'{}' can't be a name: it means something else in Glenside",
+            name
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Cell {
+    source: String,
+    /// The name to bind the cell's result to. Empty if the result isn't
+    /// bound.
+    target: String,
+    result_text: String,
+    result_tensor: Option<Rc<ArrayD<f64>>>,
+    /// The name under which the cell's result was last bound, if any. This is
+    /// the name which needs removing from the environment when the cell's
+    /// target is changed or the cell is deleted.
+    bound_name: Option<String>,
+}
+
+/// Evaluates `cell` in `environment`, binding its result into `environment`
+/// if the cell has a target, so that cells evaluated after it in the same go
+/// can use it before the parent passes it back down. Returns the binding, to
+/// be passed on to the parent.
+///
+/// If the cell fails, or its result can't be bound, whatever it bound last
+/// time is removed from `environment` instead, so that later cells don't use
+/// a stale value. The caller is left to remove it from the parent's
+/// environment too.
+///
+/// `notebook_names` are the names bound by the notebook's cells. Other
+/// tensors in `environment`, such as the user's inputs, aren't the notebook's
+/// to replace, so a cell can't bind their names.
+fn evaluate_cell(
+    cell: &mut Cell,
+    environment: &mut TensorEnvironment,
+    notebook_names: &HashSet<String>,
+) -> Option<(String, ArrayD<f64>)> {
+    let binding = bind_result(cell, environment, notebook_names);
+    if binding.is_none() {
+        if let Some(name) = &cell.bound_name {
+            environment.remove(name);
+        }
+    }
+    binding
+}
+
+/// The part of [`evaluate_cell`] which evaluates the cell and binds its
+/// result, if it can.
+fn bind_result(
+    cell: &mut Cell,
+    environment: &mut TensorEnvironment,
+    notebook_names: &HashSet<String>,
+) -> Option<(String, ArrayD<f64>)> {
+    let value = match evaluation::evaluate(&cell.source, &interpreter_environment(environment)) {
+        Ok(value) => value,
+        Err(e) => {
+            cell.result_text = e.to_string();
+            cell.result_tensor = None;
+            return None;
+        }
+    };
+    cell.result_text = result_format::format_value(&value);
+    cell.result_tensor = match value {
+        Value::Tensor(t) | Value::Access(Access { tensor: t, .. }) => Some(Rc::new(t)),
+        _ => None,
+    };
+
+    let target = cell.target.trim().to_string();
+    if target.is_empty() {
+        return None;
+    }
+    if let Err(e) = check_target(&target) {
+        cell.result_text.push_str(&format!("\n\nnot bound: {}", e));
+        return None;
+    }
+    if environment.contains_key(&target) && !notebook_names.contains(&target) {
+        cell.result_text.push_str(&format!(
+            "\n\nnot bound: '{}' is already in the environment",
+            target
+        ));
+        return None;
+    }
+    match &cell.result_tensor {
+        Some(tensor) => {
+            environment.insert(target.clone(), tensor.clone());
+            Some((target, (**tensor).clone()))
+        }
+        None => {
+            cell.result_text
+                .push_str("\n\nnot bound: only tensors can be put in the environment");
+            None
+        }
+    }
+}
+
+/// The names which `cells` have bound.
+fn bound_names(cells: &[Cell]) -> HashSet<String> {
+    cells
+        .iter()
+        .filter_map(|cell| cell.bound_name.clone())
+        .collect()
+}
+
+#[derive(Properties, Clone)]
+pub struct NotebookProps {
+    /// The environment the cells are evaluated in.
+    pub environment: Rc<TensorEnvironment>,
+    /// Called with a cell's target and result when the cell is evaluated.
+    pub value_updated_callback: yew::Callback<(String, ArrayD<f64>)>,
+    /// Called with the name a cell's result was bound to when it should be
+    /// removed from the environment.
+    pub value_removed_callback: yew::Callback<String>,
+}
+
+pub struct Notebook {
+    props: NotebookProps,
+    link: ComponentLink<Self>,
+    cells: Vec<Cell>,
+}
+
+pub enum NotebookMessage {
+    AddCell,
+    RemoveCell(usize),
+    UpdateSource(usize, String),
+    UpdateTarget(usize, String),
+    Evaluate(usize),
+    /// Evaluate every cell, in order.
+    EvaluateAll,
+}
+
+impl Notebook {
+    /// Tells the parent to remove the result cell `i` last bound, if any.
+    fn unbind(&mut self, i: usize) {
+        if let Some(name) = self.cells[i].bound_name.take() {
+            self.props.value_removed_callback.emit(name);
+        }
+    }
+
+    /// Evaluates cell `i` in `environment`, as [`evaluate_cell`] does, and
+    /// tells the parent about any change to what the cell binds.
+    fn evaluate(&mut self, i: usize, environment: &mut TensorEnvironment) {
+        let notebook_names = bound_names(&self.cells);
+        match evaluate_cell(&mut self.cells[i], environment, &notebook_names) {
+            Some((name, tensor)) => {
+                self.cells[i].bound_name = Some(name.clone());
+                self.props.value_updated_callback.emit((name, tensor));
+            }
+            None => self.unbind(i),
+        }
+    }
+
+    fn view_cell(&self, i: usize, cell: &Cell) -> Html {
+        html! {
+            <div class={"cell"} style={"border: 1px solid #888; padding: 4px; margin-bottom: 4px"}>
+                <textarea
+                    style={"width: 500px; height: 60px; font-family: monospace"}
+                    placeholder={"e.g. (compute relu (access-tensor layer1))"}
+                    value={&cell.source}
+                    oninput=self.link.callback(move |event: InputData| {
+                        NotebookMessage::UpdateSource(i, event.value)
+                    }) />
+                <br/>
+                <label>
+                    {"Bind result to: "}
+                    <input type={"text"} placeholder={"(optional name)"} value={&cell.target}
+                        oninput=self.link.callback(move |event: InputData| {
+                            NotebookMessage::UpdateTarget(i, event.value)
+                        }) />
+                </label>
+                <input type={"button"} value={"evaluate"}
+                    onclick=self.link.callback(move |_| NotebookMessage::Evaluate(i)) />
+                <input type={"button"} value={"delete"}
+                    onclick=self.link.callback(move |_| NotebookMessage::RemoveCell(i)) />
+                <pre>{&cell.result_text}</pre>
+                {
+                    match &cell.result_tensor {
+                        Some(tensor) => html! {
+                            <details>
+                                <summary>{"values"}</summary>
+                                <tensor_inspector::TensorInspector tensor=tensor.clone() />
+                            </details>
+                        },
+                        None => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+}
+
+impl Component for Notebook {
+    type Message = NotebookMessage;
+    type Properties = NotebookProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            props,
+            link,
+            cells: vec![Cell::default()],
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            NotebookMessage::AddCell => self.cells.push(Cell::default()),
+            NotebookMessage::RemoveCell(i) => {
+                self.unbind(i);
+                self.cells.remove(i);
+            }
+            NotebookMessage::UpdateSource(i, source) => {
+                self.cells[i].source = source;
+                // The textarea already shows the new source.
+                return false;
+            }
+            NotebookMessage::UpdateTarget(i, target) => {
+                self.unbind(i);
+                self.cells[i].target = target;
+                return false;
+            }
+            NotebookMessage::Evaluate(i) => {
+                let mut environment = (*self.props.environment).clone();
+                self.evaluate(i, &mut environment);
+            }
+            NotebookMessage::EvaluateAll => {
+                let mut environment = (*self.props.environment).clone();
+                for i in 0..self.cells.len() {
+                    self.evaluate(i, &mut environment);
+                }
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        false
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div>
+                { for self.cells.iter().enumerate().map(|(i, cell)| self.view_cell(i, cell)) }
+                <input type={"button"} value={"add cell"}
+                    onclick=self.link.callback(|_| NotebookMessage::AddCell) />
+                <input type={"button"} value={"evaluate all"}
+                    onclick=self.link.callback(|_| NotebookMessage::EvaluateAll) />
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::IxDyn;
+
+    fn cell(source: &str, target: &str) -> Cell {
+        Cell {
+            source: source.to_string(),
+            target: target.to_string(),
+            ..Cell::default()
+        }
+    }
+
+    /// Evaluates cell `i`, recording its binding as [`Notebook::evaluate`]
+    /// does.
+    fn evaluate(cells: &mut [Cell], i: usize, environment: &mut TensorEnvironment) {
+        let notebook_names = bound_names(cells);
+        cells[i].bound_name =
+            evaluate_cell(&mut cells[i], environment, &notebook_names).map(|(name, _)| name);
+    }
+
+    fn matrix(shape: &[usize], values: Vec<f64>) -> Rc<ArrayD<f64>> {
+        Rc::new(ArrayD::from_shape_vec(IxDyn(shape), values).unwrap())
+    }
+
+    /// Cells binding a dense layer, a relu and another dense layer, each using
+    /// the result of the one before.
+    #[test]
+    fn dense_relu_dense() {
+        let dense = |input: &str, weights: &str| {
+            format!(
+                "(compute dot-product (access-cartesian-product \
+                 (access (access-tensor {}) 1) (access (access-tensor {}) 1)))",
+                input, weights
+            )
+        };
+        let mut environment = TensorEnvironment::new();
+        environment.insert("x".to_string(), matrix(&[1, 2], vec![1., -1.]));
+        environment.insert(
+            "w1".to_string(),
+            matrix(&[3, 2], vec![1., 0., 0., 1., 1., 1.]),
+        );
+        environment.insert("w2".to_string(), matrix(&[1, 3], vec![1., 2., 3.]));
+
+        let mut cells = vec![
+            cell(&dense("x", "w1"), "h1"),
+            cell("(compute relu (access-tensor h1))", "h2"),
+            cell(&dense("h2", "w2"), "y"),
+        ];
+        for i in 0..cells.len() {
+            evaluate(&mut cells, i, &mut environment);
+        }
+
+        assert_eq!(environment["h1"], matrix(&[1, 3], vec![1., -1., 0.]));
+        assert_eq!(environment["h2"], matrix(&[1, 3], vec![1., 0., 0.]));
+        assert_eq!(environment["y"], matrix(&[1, 1], vec![1.]));
+        assert_eq!(cells[2].bound_name, Some("y".to_string()));
+        assert_eq!(cells[2].result_tensor.as_ref(), Some(&environment["y"]));
+
+        // A cell which fails takes its old result out of the environment, and
+        // the cells using it fail in turn.
+        cells[0].source = "(access-tensor nothing)".to_string();
+        for i in 0..cells.len() {
+            evaluate(&mut cells, i, &mut environment);
+        }
+        assert!(cells.iter().all(|cell| cell.bound_name.is_none()));
+        assert!(cells.iter().all(|cell| cell.result_tensor.is_none()));
+        for name in &["h1", "h2", "y"] {
+            assert!(!environment.contains_key(*name));
+        }
+
+        // As does one whose result isn't a tensor.
+        cells.push(cell("(access-tensor x)", "z"));
+        evaluate(&mut cells, 3, &mut environment);
+        assert!(environment.contains_key("z"));
+        cells[3].source = "(shape 1 2)".to_string();
+        evaluate(&mut cells, 3, &mut environment);
+        assert!(!environment.contains_key("z"));
+        assert!(cells[3]
+            .result_text
+            .ends_with("only tensors can be put in the environment"));
+
+        // Tensors the notebook didn't bind can't be replaced.
+        cells.push(cell("(access-tensor w1)", "x"));
+        evaluate(&mut cells, 4, &mut environment);
+        assert_eq!(environment["x"], matrix(&[1, 2], vec![1., -1.]));
+        assert_eq!(cells[4].bound_name, None);
+        assert!(cells[4]
+            .result_text
+            .ends_with("'x' is already in the environment"));
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(check_target("layer1"), Ok(()));
+        assert_eq!(
+            check_target("layer 1").unwrap_err(),
+            "'layer 1' can't be a name: names can't contain spaces or parentheses"
+        );
+        assert_eq!(
+            check_target("2").unwrap_err(),
+            "'2' can't be a name: it's a number"
+        );
+        for keyword in &["relu", "zero-padding", "access-tensor"] {
+            assert_eq!(
+                check_target(keyword).unwrap_err(),
+                format!(
+                    "'{}' can't be a name: it means something else in Glenside",
+                    keyword
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Reading and writing of NumPy's `.npy` and `.npz` files. See
+//! <https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html>.
+
+use ndarray::{ArrayD, IxDyn, ShapeBuilder};
+use std::io::{Cursor, Read, Write};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Finds the value of `key` in an `.npy` header, which is a Python dict
+/// literal such as `{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }`.
+/// The value is returned as written, up to the `,` or `}` which ends it.
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, String> {
+    let start = header
+        .find(&format!("'{}':", key))
+        .ok_or_else(|| format!("the .npy header has no '{}'", key))?
+        + key.len()
+        + 3;
+    let rest = header[start..].trim_start();
+    // Shapes are tuples, which contain commas of their own.
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|i| i + 1)
+    } else {
+        rest.find(&[',', '}'][..])
+    }
+    .ok_or_else(|| format!("the .npy header's '{}' is malformed", key))?;
+    Ok(rest[..end].trim())
+}
+
+fn parse_shape(shape: &str) -> Result<Vec<usize>, String> {
+    shape
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| {
+            dim.parse()
+                .map_err(|_| format!("invalid dimension '{}' in .npy shape", dim))
+        })
+        .collect()
+}
+
+/// Converts the little-endian elements in `data`, of the type described by
+/// `descr`, to `f64`s.
+fn parse_data(descr: &str, data: &[u8]) -> Result<Vec<f64>, String> {
+    macro_rules! convert {
+        ($t:ty) => {
+            data.chunks_exact(std::mem::size_of::<$t>())
+                .map(|bytes| {
+                    let mut array = [0; std::mem::size_of::<$t>()];
+                    array.copy_from_slice(bytes);
+                    <$t>::from_le_bytes(array) as f64
+                })
+                .collect()
+        };
+    }
+
+    Ok(match descr {
+        "<f8" => convert!(f64),
+        "<f4" => convert!(f32),
+        "|i1" => convert!(i8),
+        "<i2" => convert!(i16),
+        "<i4" => convert!(i32),
+        "<i8" => convert!(i64),
+        "|u1" | "|b1" => convert!(u8),
+        "<u2" => convert!(u16),
+        "<u4" => convert!(u32),
+        "<u8" => convert!(u64),
+        _ if descr.starts_with('>') => {
+            return Err(format!(
+                "big-endian arrays ('{}') aren't supported; convert the array \
+                 with .astype('<{}') first",
+                descr,
+                &descr[1..]
+            ))
+        }
+        _ => return Err(format!("unsupported .npy data type '{}'", descr)),
+    })
+}
+
+/// Parses the contents of a `.npy` file.
+pub fn parse_npy(bytes: &[u8]) -> Result<ArrayD<f64>, String> {
+    if !bytes.starts_with(MAGIC) || bytes.len() < 10 {
+        return Err("not a .npy file".to_string());
+    }
+    // Version 1 has a two-byte header length; later versions have four.
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        2 | 3 if bytes.len() >= 12 => (
+            12,
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+        ),
+        version => return Err(format!("unsupported .npy version {}", version)),
+    };
+    let header = bytes
+        .get(header_start..header_start + header_len)
+        .ok_or_else(|| "the .npy file is truncated".to_string())?;
+    let header = std::str::from_utf8(header).map_err(|_| "invalid .npy header".to_string())?;
+
+    let descr = header_value(header, "descr")?.trim_matches('\'');
+    let fortran_order = header_value(header, "fortran_order")? == "True";
+    let shape = parse_shape(header_value(header, "shape")?)?;
+
+    let values = parse_data(descr, &bytes[header_start + header_len..])?;
+    let len = shape
+        .iter()
+        .try_fold(1usize, |len, &dim| len.checked_mul(dim))
+        .ok_or_else(|| "the .npy file's shape is too large".to_string())?;
+    if values.len() < len {
+        return Err(format!(
+            "the .npy file is truncated: its shape needs {} elements, but it only has {}",
+            len,
+            values.len()
+        ));
+    }
+
+    let shape = IxDyn(&shape);
+    let shape = if fortran_order {
+        shape.f()
+    } else {
+        shape.into()
+    };
+    // ndarray has limits of its own, e.g. on the size of shapes with a
+    // zero-length dimension.
+    ArrayD::from_shape_vec(shape, values[..len].to_vec())
+        .map_err(|e| format!("the .npy file's shape can't be used: {}", e))
+}
+
+/// Parses the contents of a `.npz` file: a zip archive of `.npy` files.
+/// Returns each array along with its name, which is the name of its `.npy`
+/// file without the extension.
+pub fn parse_npz(bytes: &[u8]) -> Result<Vec<(String, ArrayD<f64>)>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("invalid .npz file: {}", e))?;
+    (0..archive.len())
+        .map(|i| {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|e| format!("invalid .npz file: {}", e))?;
+            let name = file.name().trim_end_matches(".npy").to_string();
+            let mut contents = vec![];
+            file.read_to_end(&mut contents)
+                .map_err(|e| format!("couldn't read {} from the .npz file: {}", name, e))?;
+            let array = parse_npy(&contents).map_err(|e| format!("{}: {}", name, e))?;
+            Ok((name, array))
+        })
+        .collect()
+}
+
+/// Serializes `array` as a `.npy` file of `f64`s.
+pub fn write_npy(array: &ArrayD<f64>) -> Vec<u8> {
+    let shape = match array.shape() {
+        // One-element tuples need a trailing comma.
+        [dim] => format!("({},)", dim),
+        shape => format!(
+            "({})",
+            shape
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}",
+        shape
+    );
+    // The header is padded with spaces and ends with a newline, so that the
+    // data is aligned to 64 bytes.
+    let unpadded_len = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded_len % 64) % 64));
+    header.push('\n');
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(&[1, 0]);
+    bytes.extend(&(header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    for value in array.iter() {
+        bytes.extend(&value.to_le_bytes());
+    }
+    bytes
+}
+
+/// Serializes `arrays` as a `.npz` file, as written by `numpy.savez`.
+pub fn write_npz<'a>(arrays: impl IntoIterator<Item = (&'a str, &'a ArrayD<f64>)>) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    for (name, array) in arrays {
+        zip.start_file(
+            format!("{}.npy", name),
+            FileOptions::default().compression_method(CompressionMethod::Stored),
+        )
+        .unwrap();
+        zip.write_all(&write_npy(array)).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Dimension;
+
+    /// Builds a version 1 `.npy` file.
+    fn npy(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(&[1, 0]);
+        bytes.extend(&(header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn parse() {
+        let data = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parse_npy(&npy(
+                "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }",
+                &data
+            ))
+            .unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 3]), vec![1., 2., 3., 4., 5., 6.]).unwrap()
+        );
+        assert_eq!(
+            parse_npy(&npy(
+                "{'descr': '<f4', 'fortran_order': True, 'shape': (2, 3), }",
+                &data
+            ))
+            .unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 3]), vec![1., 3., 5., 2., 4., 6.]).unwrap()
+        );
+        assert_eq!(
+            parse_npy(&npy(
+                "{'descr': '<i8', 'fortran_order': False, 'shape': (), }",
+                &(-3i64).to_le_bytes()
+            ))
+            .unwrap(),
+            ArrayD::from_elem(IxDyn(&[]), -3.)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for shape in &[&[][..], &[3], &[2, 3, 4]] {
+            let array = ArrayD::from_shape_fn(IxDyn(shape), |index| {
+                index.slice().iter().sum::<usize>() as f64
+            });
+            let bytes = write_npy(&array);
+            assert_eq!((bytes.len() - array.len() * 8) % 64, 0);
+            assert_eq!(parse_npy(&bytes).unwrap(), array);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_npy(&npy(
+                "{'descr': '>f8', 'fortran_order': False, 'shape': (1,), }",
+                &[0; 8]
+            ))
+            .unwrap_err(),
+            "big-endian arrays ('>f8') aren't supported; convert the array with \
+             .astype('<f8') first"
+        );
+        assert_eq!(
+            parse_npy(&npy(
+                "{'descr': '<f8', 'fortran_order': False, 'shape': (2,), }",
+                &[0; 8]
+            ))
+            .unwrap_err(),
+            "the .npy file is truncated: its shape needs 2 elements, but it only has 1"
+        );
+        assert_eq!(
+            parse_npy(&npy(
+                "{'descr': '<f8', 'fortran_order': False, 'shape': (4294967296, 4294967296), }",
+                &[0; 8]
+            ))
+            .unwrap_err(),
+            "the .npy file's shape is too large"
+        );
+        assert!(parse_npy(&npy(
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (0, 9223372036854775808), }",
+            &[]
+        ))
+        .unwrap_err()
+        .starts_with("the .npy file's shape can't be used: "));
+    }
+}
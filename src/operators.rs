@@ -0,0 +1,299 @@
+//! The vocabulary of the Glenside language, as far as the editor is
+//! concerned.
+
+/// Documentation for one of Glenside's operators: the things which can appear
+/// at the head of an s-expression.
+pub struct Operator {
+    pub name: &'static str,
+    /// The names of the operator's arguments, in order.
+    pub arguments: &'static [&'static str],
+    /// What the operator does.
+    pub description: &'static str,
+    /// What the operator produces, in terms of its arguments.
+    pub result: &'static str,
+}
+
+impl Operator {
+    /// The operator applied to its argument names, e.g.
+    /// `(access access axis)`.
+    pub fn signature(&self) -> String {
+        format!(
+            "({})",
+            std::iter::once(self.name)
+                .chain(self.arguments.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    }
+
+    /// A Monaco snippet inserting the operator followed by placeholders for
+    /// its arguments. The opening paren is assumed to be typed already.
+    pub fn snippet(&self) -> String {
+        std::iter::once(self.name.to_string())
+            .chain(
+                self.arguments
+                    .iter()
+                    .enumerate()
+                    .map(|(i, argument)| format!("${{{}:{}}}", i + 1, argument)),
+            )
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Markdown documentation, as shown in hover cards and completions.
+    pub fn documentation(&self) -> String {
+        format!("{}\n\n**Result:** {}", self.description, self.result)
+    }
+}
+
+pub const OPERATORS: &[Operator] = &[
+    Operator {
+        name: "access-tensor",
+        arguments: &["tensor"],
+        description: "Views `tensor` as an access pattern with a single item: the whole tensor.",
+        result: "An access pattern of shape `((), (tensor shape))`.",
+    },
+    Operator {
+        name: "access",
+        arguments: &["access", "axis"],
+        description: "Re-accesses `access` at dimension `axis`. Dimensions before `axis` \
+                      are iterated over; dimensions from `axis` onwards make up each item.",
+        result: "For a tensor of shape `(a, b, c)` accessed at axis 1, an access pattern \
+                 of shape `((a), (b, c))`.",
+    },
+    Operator {
+        name: "access-transpose",
+        arguments: &["access", "list"],
+        description: "Permutes the dimensions of `access` according to the `list` \
+                      of dimension indices. The access axis stays where it is.",
+        result: "An access pattern whose dimensions are reordered by `list`.",
+    },
+    Operator {
+        name: "access-cartesian-product",
+        arguments: &["access-a", "access-b"],
+        description: "Pairs every item of `access-a` with every item of `access-b`. \
+                      The items of both access patterns must have the same shape.",
+        result: "For arguments of shape `((a), (i))` and `((b), (i))`, an access pattern \
+                 of shape `((a, b), (2, i))`.",
+    },
+    Operator {
+        name: "compute",
+        arguments: &["compute-type", "access"],
+        description: "Applies the computation `compute-type` (e.g. `dot-product`, \
+                      `reduce-sum`, `relu`) to each item of `access`.",
+        result: "An access pattern with the same iteration dimensions as `access`. \
+                 Reductions such as `dot-product` and `reduce-sum` produce scalar items; \
+                 elementwise computations such as `relu` keep the item shape.",
+    },
+    Operator {
+        name: "access-windows",
+        arguments: &["access", "window-shape", "strides"],
+        description: "Forms windows of shape `window-shape` over the tensor accessed by \
+                      `access`, moving by `strides` in each dimension. Both are `shape` \
+                      literals.",
+        result: "An access pattern of shape `((number of window positions in each \
+                 dimension), (window-shape))`.",
+    },
+    Operator {
+        name: "access-pad",
+        arguments: &["access", "pad-type", "axis", "before", "after"],
+        description: "Pads dimension `axis` of `access` with `before` elements at the start \
+                      and `after` elements at the end, using `pad-type` (e.g. \
+                      `zero-padding`).",
+        result: "An access pattern whose dimension `axis` is `before + after` larger.",
+    },
+    Operator {
+        name: "access-squeeze",
+        arguments: &["access", "axis"],
+        description: "Removes dimension `axis` of `access`, which must have length 1.",
+        result: "An access pattern with one dimension fewer.",
+    },
+    Operator {
+        name: "access-insert-axis",
+        arguments: &["access", "axis"],
+        description: "Inserts a new dimension of length 1 into `access` at `axis`.",
+        result: "An access pattern with one dimension more.",
+    },
+    Operator {
+        name: "access-broadcast",
+        arguments: &["access", "access-shape"],
+        description: "Broadcasts `access` to the shape given by the `access-shape` literal.",
+        result: "An access pattern of shape `access-shape`.",
+    },
+    Operator {
+        name: "access-flatten",
+        arguments: &["access"],
+        description: "Flattens the iteration dimensions of `access` into one dimension, \
+                      and its item dimensions into another.",
+        result: "For an argument of shape `((a, b), (c, d))`, an access pattern of shape \
+                 `((a * b), (c * d))`.",
+    },
+    Operator {
+        name: "access-reshape",
+        arguments: &["access", "access-shape"],
+        description: "Reshapes `access` to the shape given by the `access-shape` literal, \
+                      which must contain the same number of elements.",
+        result: "An access pattern of shape `access-shape`.",
+    },
+    Operator {
+        name: "access-slice",
+        arguments: &["access", "axis", "low", "high"],
+        description: "Keeps only the indices from `low` (inclusive) to `high` (exclusive) \
+                      of dimension `axis` of `access`.",
+        result: "An access pattern whose dimension `axis` has length `high - low`.",
+    },
+    Operator {
+        name: "access-concatenate",
+        arguments: &["access-a", "access-b", "axis"],
+        description: "Concatenates `access-a` and `access-b` along dimension `axis`. All \
+                      other dimensions must match.",
+        result: "An access pattern whose dimension `axis` is the sum of the arguments' \
+                 lengths along that dimension.",
+    },
+    Operator {
+        name: "access-pair",
+        arguments: &["access-a", "access-b"],
+        description: "Pairs up corresponding items of `access-a` and `access-b`, which must \
+                      have the same shape.",
+        result: "For arguments of shape `((a), (i))`, an access pattern of shape \
+                 `((a), (2, i))`.",
+    },
+    Operator {
+        name: "access-shift-right",
+        arguments: &["access"],
+        description: "Moves the access axis of `access` one dimension to the left, so that \
+                      the last iteration dimension becomes the first item dimension.",
+        result: "For an argument of shape `((a, b), (c))`, an access pattern of shape \
+                 `((a), (b, c))`.",
+    },
+    Operator {
+        name: "access-shape",
+        arguments: &["shape", "item-shape"],
+        description: "An access pattern shape literal, built from two `shape` literals.",
+        result: "The access pattern shape `((shape), (item-shape))`.",
+    },
+    Operator {
+        name: "shape",
+        arguments: &["dims..."],
+        description: "A shape literal, e.g. `(shape 1 3 3 3)`.",
+        result: "The shape `(dims...)`.",
+    },
+    Operator {
+        name: "list",
+        arguments: &["items..."],
+        description: "A list literal, e.g. the permutation `(list 1 0)` given to \
+                      `access-transpose`.",
+        result: "The list `(items...)`.",
+    },
+    Operator {
+        name: "get-access-shape",
+        arguments: &["access"],
+        description: "Gets the shape of `access` as an access pattern shape literal.",
+        result: "The access pattern shape of `access`.",
+    },
+    Operator {
+        name: "shape-of",
+        arguments: &["tensor"],
+        description: "Gets the shape of `tensor` as a shape literal.",
+        result: "The shape of `tensor`.",
+    },
+    Operator {
+        name: "slice-shape",
+        arguments: &["shape", "axis"],
+        description: "Drops the dimensions of `shape` before `axis`.",
+        result: "The dimensions of `shape` from `axis` onwards.",
+    },
+    Operator {
+        name: "shape-insert-axis",
+        arguments: &["shape", "axis"],
+        description: "Inserts a dimension of length 1 into `shape` at `axis`.",
+        result: "A shape with one dimension more.",
+    },
+    Operator {
+        name: "shape-remove-axis",
+        arguments: &["shape", "axis"],
+        description: "Removes dimension `axis` from `shape`.",
+        result: "A shape with one dimension fewer.",
+    },
+];
+
+/// The computations which can be passed to `compute`.
+pub const COMPUTE_TYPES: &[&str] = &[
+    "dot-product",
+    "reduce-sum",
+    "reduce-max",
+    "reduce-mean",
+    "relu",
+    "sqrt",
+    "negative",
+    "elementwise-add",
+    "elementwise-mul",
+    "elementwise-div",
+    "softmax",
+];
+
+/// The kinds of padding which can be passed to `access-pad`.
+pub const PAD_TYPES: &[&str] = &["zero-padding", "min-padding"];
+
+/// Looks up the documentation for the operator called `name`.
+pub fn find_operator(name: &str) -> Option<&'static Operator> {
+    OPERATORS.iter().find(|operator| operator.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egg::RecExpr;
+    use glenside::language::{ComputeType, Language, PadType};
+    use std::str::FromStr;
+
+    #[test]
+    fn signature_and_snippet() {
+        assert_eq!(
+            find_operator("access").unwrap().signature(),
+            "(access access axis)"
+        );
+        let windows = find_operator("access-windows").unwrap();
+        assert_eq!(
+            windows.signature(),
+            "(access-windows access window-shape strides)"
+        );
+        assert_eq!(
+            windows.snippet(),
+            "access-windows ${1:access} ${2:window-shape} ${3:strides}"
+        );
+    }
+
+    #[test]
+    fn names_are_glensides() {
+        for compute_type in COMPUTE_TYPES {
+            assert!(
+                ComputeType::from_str(compute_type).is_ok(),
+                "{} isn't a compute type",
+                compute_type
+            );
+        }
+        for pad_type in PAD_TYPES {
+            assert!(
+                PadType::from_str(pad_type).is_ok(),
+                "{} isn't a pad type",
+                pad_type
+            );
+        }
+        for operator in OPERATORS {
+            // The operator applied to a placeholder for each argument.
+            let skeleton = format!(
+                "({})",
+                std::iter::once(operator.name)
+                    .chain(operator.arguments.iter().map(|_| "x"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+            assert!(
+                RecExpr::<Language>::from_str(&skeleton).is_ok(),
+                "{} doesn't parse",
+                skeleton
+            );
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! A collapsible tree view of a parsed Glenside program.
+
+use crate::evaluation;
+use crate::shape_inference;
+use crate::spans::{self, Span};
+use egg::Language as _;
+use std::collections::HashMap;
+use std::rc::Rc;
+use yew::events::MouseEvent;
+use yew::{html, Component, ComponentLink, Html, Properties, ShouldRender};
+
+/// A node of the program, as displayed in the tree.
+pub struct TreeNode {
+    /// The node's operator, or the symbol or literal it holds.
+    pub label: String,
+    /// The ids of the node's children.
+    pub children: Vec<usize>,
+    /// Where the node is in the source.
+    pub span: Span,
+    /// The node's inferred shape, if it has one and inference succeeded.
+    pub shape: Option<String>,
+}
+
+/// Builds the tree for the program in `source`, indexed by node id. `shapes`
+/// maps the names in the environment to the shapes of their tensors. Returns
+/// `None` if the program doesn't parse.
+pub fn build_tree(source: &str, shapes: &HashMap<String, Vec<usize>>) -> Option<Vec<TreeNode>> {
+    let expr = evaluation::parse(source).ok()?;
+    let spans = spans::node_spans(source)?;
+    // This runs on every cursor move, so it mustn't panic on the ill-shaped
+    // programs seen while typing. `infer_shapes` checks the shapes before
+    // inferring them; if the check fails, show the tree without shapes.
+    let data = shape_inference::infer_shapes(&expr, shapes).ok();
+
+    Some(
+        expr.as_ref()
+            .iter()
+            .zip(spans)
+            .enumerate()
+            .map(|(id, (node, span))| TreeNode {
+                label: node.to_string(),
+                children: node.children().iter().map(|&c| usize::from(c)).collect(),
+                span,
+                shape: data
+                    .as_ref()
+                    .and_then(|data| shape_inference::describe(&data[id])),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Properties, Clone)]
+pub struct ProgramTreeProps {
+    /// The nodes of the program, as returned by [`build_tree`].
+    pub nodes: Rc<Vec<TreeNode>>,
+    /// The id of the node to highlight.
+    #[prop_or_default]
+    pub selected: Option<usize>,
+    /// Called with a node's id when the user clicks on it.
+    pub on_select: yew::Callback<usize>,
+}
+
+pub struct ProgramTree {
+    props: ProgramTreeProps,
+}
+
+impl ProgramTree {
+    fn view_node(&self, id: usize) -> Html {
+        let node = &self.props.nodes[id];
+        let style = if self.props.selected == Some(id) {
+            "cursor: pointer; background-color: #ddd"
+        } else {
+            "cursor: pointer"
+        };
+        let label = html! {
+            <span style={style}
+                onclick=self.props.on_select.reform(move |e: MouseEvent| {
+                    // Don't also collapse/expand the node.
+                    e.prevent_default();
+                    id
+                })>
+                <code>{&node.label}</code>
+                {node.shape.as_ref().map(|shape| format!(" {}", shape)).unwrap_or_default()}
+            </span>
+        };
+
+        if node.children.is_empty() {
+            html! {
+                <div style={"margin-left: 1em"}>{label}</div>
+            }
+        } else {
+            html! {
+                <details open=true style={"margin-left: 1em"}>
+                    <summary>{label}</summary>
+                    { for node.children.iter().map(|&child| self.view_node(child)) }
+                </details>
+            }
+        }
+    }
+}
+
+impl Component for ProgramTree {
+    type Message = ();
+    type Properties = ProgramTreeProps;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        unreachable!()
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        match self.props.nodes.len() {
+            0 => html! {},
+            // The root is the last node.
+            n => self.view_node(n - 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ill_shaped() {
+        let mut shapes = HashMap::new();
+        shapes.insert("t".to_string(), vec![2, 3]);
+
+        let nodes = build_tree("(access (access-tensor t) 1)", &shapes).unwrap();
+        assert_eq!(nodes[3].shape, Some("((2), (3))".to_string()));
+
+        let nodes = build_tree("(access (access-tensor t) 5)", &shapes).unwrap();
+        assert_eq!(nodes[3].label, "access");
+        assert_eq!(nodes[3].children, vec![1, 2]);
+        assert!(nodes.iter().all(|node| node.shape.is_none()));
+    }
+}
@@ -0,0 +1,117 @@
+//! Human-readable rendering of interpreter results. The values of tensors
+//! aren't included; they're shown by the
+//! [`TensorInspector`](crate::tensor_inspector::TensorInspector) instead.
+
+use glenside::language::interpreter::Value;
+use ndarray::Dimension;
+
+/// Formats a list of dimensions as a comma-separated list, without the
+/// surrounding parentheses. For example, `[2, 3]` becomes `"2, 3"`.
+pub fn format_dims(dims: &[usize]) -> String {
+    dims.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats the shape of an access pattern, given the full shape of the
+/// accessed tensor and the access axis. For example, shape `[2, 3]` with
+/// access axis `1` becomes `"((2), (3))"`.
+pub fn format_access_shape(shape: &[usize], access_axis: usize) -> String {
+    format!(
+        "(({}), ({}))",
+        format_dims(&shape[..access_axis]),
+        format_dims(&shape[access_axis..])
+    )
+}
+
+/// Produces the text shown in the result box for an interpreted value.
+pub fn format_value(value: &Value<f64>) -> String {
+    match value {
+        Value::Tensor(t) => format!("tensor with shape:\n({})", format_dims(t.shape())),
+        Value::Access(a) => format!(
+            "access pattern with shape:\n{}",
+            format_access_shape(a.tensor.shape(), a.access_axis)
+        ),
+        Value::Usize(u) => format!("usize literal with value:\n{}", u),
+        Value::Shape(shape) => {
+            format!(
+                "shape literal with value:\n({})",
+                format_dims(shape.slice())
+            )
+        }
+        Value::ComputeType(t) => format!("compute type:\n{}", t),
+        Value::PadType(t) => format!("pad type:\n{}", t),
+        Value::AccessShape(shape, access_axis) => format!(
+            "access pattern shape literal with value:\n{}",
+            format_access_shape(shape.slice(), *access_axis)
+        ),
+        Value::List(l) => format!("list literal with value:\n({})", format_dims(l)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glenside::language::interpreter::Access;
+    use ndarray::{ArrayD, IxDyn};
+
+    #[test]
+    fn tensor() {
+        let t = ArrayD::from_shape_vec(IxDyn(&[2, 2]), vec![1., 2., 3., 4.]).unwrap();
+        assert_eq!(
+            format_value(&Value::Tensor(t)),
+            "tensor with shape:\n(2, 2)"
+        );
+    }
+
+    #[test]
+    fn access() {
+        let t = ArrayD::zeros(IxDyn(&[2, 3]));
+        assert_eq!(
+            format_value(&Value::Access(Access {
+                tensor: t,
+                access_axis: 1
+            })),
+            "access pattern with shape:\n((2), (3))"
+        );
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(
+            format_value(&Value::Usize(3)),
+            "usize literal with value:\n3"
+        );
+        assert_eq!(
+            format_value(&Value::Shape(IxDyn(&[1, 2]))),
+            "shape literal with value:\n(1, 2)"
+        );
+        assert_eq!(
+            format_value(&Value::Shape(IxDyn(&[]))),
+            "shape literal with value:\n()"
+        );
+        assert_eq!(
+            format_value(&Value::List(vec![1, 0])),
+            "list literal with value:\n(1, 0)"
+        );
+        assert_eq!(
+            format_value(&Value::AccessShape(IxDyn(&[2, 3]), 0)),
+            "access pattern shape literal with value:\n((), (2, 3))"
+        );
+    }
+
+    #[test]
+    fn compute_and_pad_types() {
+        assert_eq!(
+            format_value(&Value::ComputeType(
+                glenside::language::ComputeType::DotProduct
+            )),
+            "compute type:\ndot-product"
+        );
+        assert_eq!(
+            format_value(&Value::PadType(glenside::language::PadType::ZeroPadding)),
+            "pad type:\nzero-padding"
+        );
+    }
+}
@@ -0,0 +1,479 @@
+//! A check that the shapes in a Glenside program line up, which reports
+//! problems as errors rather than panicking.
+//!
+//! Glenside's interpreter and its e-graph analysis both `assert!` that shapes
+//! line up, and when built for `wasm32-unknown-unknown` a failed assertion
+//! aborts the whole app. So before a program is handed to either, the checks
+//! they make are repeated here. Operators which aren't covered come out as
+//! [`NodeShape::Unknown`], as does anything built on top of them, and
+//! programs using them are turned away by [`check_supported`] rather than
+//! risk a panic.
+//!
+//! The interpreter and analysis are still run under
+//! [`std::panic::catch_unwind`], but that's only a backstop for errors the
+//! check misses when running natively (e.g. in tests).
+
+use crate::evaluation::EvaluationError;
+use crate::result_format::{format_access_shape, format_dims};
+use egg::{Language as _, RecExpr};
+use glenside::language::Language;
+use std::collections::HashMap;
+
+/// What the check knows about the value of a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeShape {
+    /// A tensor from the environment, with the given shape.
+    Tensor(Vec<usize>),
+    /// An access pattern over a tensor of shape `dims`, accessed at `axis`.
+    Access {
+        dims: Vec<usize>,
+        axis: usize,
+    },
+    Usize(usize),
+    /// A `shape` or `list` literal.
+    List(Vec<usize>),
+    /// Any other leaf, such as a compute type or pad type.
+    Keyword(String),
+    /// A node which the check doesn't cover.
+    Unknown,
+}
+
+/// Checks the shapes of every node of `expr`, returning what is known about
+/// each, indexed by node id. `shapes` maps the names in the environment to
+/// the shapes of their tensors; every variable in `expr` is assumed to be
+/// bound (see [`check_bound`](crate::evaluation::check_bound)).
+///
+/// Nodes are checked in post-order, so an error is reported against the
+/// innermost node at fault.
+pub fn check(
+    expr: &RecExpr<Language>,
+    shapes: &HashMap<String, Vec<usize>>,
+) -> Result<Vec<NodeShape>, EvaluationError> {
+    let mut checked: Vec<NodeShape> = Vec::with_capacity(expr.as_ref().len());
+
+    for (id, node) in expr.as_ref().iter().enumerate() {
+        let arguments = node
+            .children()
+            .iter()
+            .map(|&child| &checked[usize::from(child)])
+            .collect::<Vec<_>>();
+        let shape =
+            check_node(node, &arguments, shapes).map_err(|message| EvaluationError::Shape {
+                message,
+                node: Some(id),
+            })?;
+        checked.push(shape);
+    }
+
+    Ok(checked)
+}
+
+/// Fails with [`EvaluationError::UnsupportedOperator`] if any node of `expr`
+/// is [`NodeShape::Unknown`] in `checked`, as returned by [`check`].
+pub fn check_supported(
+    expr: &RecExpr<Language>,
+    checked: &[NodeShape],
+) -> Result<(), EvaluationError> {
+    match checked
+        .iter()
+        .position(|shape| *shape == NodeShape::Unknown)
+    {
+        Some(node) => Err(unsupported_operator(expr, node)),
+        None => Ok(()),
+    }
+}
+
+/// The error for a program in which `node` is the first node which
+/// [`check`] found to be [`NodeShape::Unknown`]. Unknown nodes are contagious,
+/// so that's the node which the check doesn't cover.
+pub fn unsupported_operator(expr: &RecExpr<Language>, node: usize) -> EvaluationError {
+    let nodes = expr.as_ref();
+    let operator = match nodes[node].to_string().as_str() {
+        // Name the compute type, rather than `compute` itself.
+        "compute" => format!("compute {}", nodes[usize::from(nodes[node].children()[0])]),
+        operator => operator.to_string(),
+    };
+    EvaluationError::UnsupportedOperator { operator, node }
+}
+
+/// The operators which [`check_node`] covers.
+const CHECKED_OPERATORS: &[&str] = &[
+    "shape",
+    "list",
+    "access-tensor",
+    "access",
+    "access-transpose",
+    "access-cartesian-product",
+    "compute",
+    "access-windows",
+    "access-pad",
+    "access-squeeze",
+    "access-slice",
+    "access-concatenate",
+    "access-pair",
+];
+
+/// Checks that `axis` indexes one of `ndim` dimensions. `what` names the
+/// axis in the error message.
+fn check_axis(what: &str, axis: usize, ndim: usize) -> Result<(), String> {
+    if axis < ndim {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} {} is out of range for an access pattern with {} dimensions",
+            what, axis, ndim
+        ))
+    }
+}
+
+/// Checks a single node, given what is known about its children.
+fn check_node(
+    node: &Language,
+    arguments: &[&NodeShape],
+    shapes: &HashMap<String, Vec<usize>>,
+) -> Result<NodeShape, String> {
+    use NodeShape::*;
+
+    if let Language::Symbol(name) = node {
+        if let Some(shape) = shapes.get(name) {
+            return Ok(Tensor(shape.clone()));
+        }
+    }
+    let label = node.to_string();
+    if node.children().is_empty() {
+        return Ok(match label.parse() {
+            Ok(u) => Usize(u),
+            Err(_) => Keyword(label),
+        });
+    }
+    if arguments.iter().any(|argument| **argument == Unknown) {
+        return Ok(Unknown);
+    }
+
+    match (label.as_str(), arguments) {
+        ("shape", _) | ("list", _) => arguments
+            .iter()
+            .map(|argument| match argument {
+                Usize(u) => Ok(*u),
+                _ => Err(format!("\"{}\" takes only numbers", label)),
+            })
+            .collect::<Result<_, _>>()
+            .map(List),
+        ("access-tensor", [Tensor(dims)]) => Ok(Access {
+            dims: dims.clone(),
+            axis: 0,
+        }),
+        ("access", [Access { dims, .. }, Usize(axis)]) => {
+            // Accessing just past the last dimension gives scalar items.
+            if *axis > dims.len() {
+                return Err(format!(
+                    "access axis {} is out of range for an access pattern with {} dimensions",
+                    axis,
+                    dims.len()
+                ));
+            }
+            Ok(Access {
+                dims: dims.clone(),
+                axis: *axis,
+            })
+        }
+        ("access-transpose", [Access { dims, axis }, List(permutation)]) => {
+            let mut sorted = permutation.clone();
+            sorted.sort_unstable();
+            if sorted != (0..dims.len()).collect::<Vec<_>>() {
+                return Err(format!(
+                    "({}) is not a permutation of the {} dimensions of the access pattern",
+                    format_dims(permutation),
+                    dims.len()
+                ));
+            }
+            Ok(Access {
+                dims: permutation.iter().map(|&i| dims[i]).collect(),
+                axis: *axis,
+            })
+        }
+        (
+            "access-cartesian-product",
+            [Access {
+                dims: dims_a,
+                axis: axis_a,
+            }, Access {
+                dims: dims_b,
+                axis: axis_b,
+            }],
+        ) => {
+            let (item_a, item_b) = (&dims_a[*axis_a..], &dims_b[*axis_b..]);
+            if item_a != item_b {
+                return Err(format!(
+                    "the items of the access patterns have different shapes, ({}) and ({})",
+                    format_dims(item_a),
+                    format_dims(item_b)
+                ));
+            }
+            Ok(Access {
+                dims: dims_a[..*axis_a]
+                    .iter()
+                    .chain(&dims_b[..*axis_b])
+                    .chain(&[2])
+                    .chain(item_a)
+                    .copied()
+                    .collect(),
+                axis: axis_a + axis_b,
+            })
+        }
+        ("compute", [Keyword(compute_type), Access { dims, axis }]) => {
+            let reduces_first_item_axis = match compute_type.as_str() {
+                "dot-product" | "elementwise-add" | "elementwise-mul" | "elementwise-div" => true,
+                "reduce-sum" | "reduce-max" | "reduce-mean" | "relu" | "sqrt" | "negative"
+                | "softmax" => false,
+                _ => return Ok(Unknown),
+            };
+            if reduces_first_item_axis && *axis == dims.len() {
+                return Err(format!(
+                    "\"{}\" needs items with at least one dimension, but the items are scalars",
+                    compute_type
+                ));
+            }
+            let dims = match compute_type.as_str() {
+                "dot-product" | "reduce-sum" | "reduce-max" | "reduce-mean" => {
+                    dims[..*axis].to_vec()
+                }
+                "elementwise-add" | "elementwise-mul" | "elementwise-div" => dims
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| i != *axis)
+                    .map(|(_, &dim)| dim)
+                    .collect(),
+                _ => dims.clone(),
+            };
+            Ok(Access { dims, axis: *axis })
+        }
+        ("access-windows", [Access { dims, axis }, List(window), List(strides)]) => {
+            // Windows are formed over the iteration dimensions. Glenside only
+            // handles scalar items; anything else is left to it.
+            if *axis != dims.len() {
+                return Ok(Unknown);
+            }
+            if window.len() != dims.len() || strides.len() != dims.len() {
+                return Err(format!(
+                    "the window shape ({}) and strides ({}) must have one dimension \
+                     for each of the access pattern's {} dimensions",
+                    format_dims(window),
+                    format_dims(strides),
+                    dims.len()
+                ));
+            }
+            if strides.contains(&0) {
+                return Err("strides must be at least 1".to_string());
+            }
+            if let Some(i) = (0..dims.len()).find(|&i| window[i] > dims[i]) {
+                return Err(format!(
+                    "the window shape ({}) is larger than the access pattern's shape ({}) \
+                     in dimension {}",
+                    format_dims(window),
+                    format_dims(dims),
+                    i
+                ));
+            }
+            let positions = (0..dims.len())
+                .map(|i| (dims[i] - window[i]) / strides[i] + 1)
+                .collect::<Vec<_>>();
+            Ok(Access {
+                axis: positions.len(),
+                dims: positions
+                    .into_iter()
+                    .chain(window.iter().copied())
+                    .collect(),
+            })
+        }
+        (
+            "access-pad",
+            [Access { dims, axis }, Keyword(_), Usize(pad_axis), Usize(before), Usize(after)],
+        ) => {
+            check_axis("pad axis", *pad_axis, dims.len())?;
+            let mut dims = dims.clone();
+            dims[*pad_axis] += before + after;
+            Ok(Access { dims, axis: *axis })
+        }
+        ("access-squeeze", [Access { dims, axis }, Usize(squeeze_axis)]) => {
+            check_axis("squeeze axis", *squeeze_axis, dims.len())?;
+            if dims[*squeeze_axis] != 1 {
+                return Err(format!(
+                    "dimension {} has length {}, so it can't be squeezed",
+                    squeeze_axis, dims[*squeeze_axis]
+                ));
+            }
+            let mut dims = dims.clone();
+            dims.remove(*squeeze_axis);
+            Ok(Access {
+                dims,
+                axis: if squeeze_axis < axis { axis - 1 } else { *axis },
+            })
+        }
+        ("access-slice", [Access { dims, axis }, Usize(slice_axis), Usize(low), Usize(high)]) => {
+            check_axis("slice axis", *slice_axis, dims.len())?;
+            if low > high || *high > dims[*slice_axis] {
+                return Err(format!(
+                    "the slice from {} to {} is out of range for dimension {}, of length {}",
+                    low, high, slice_axis, dims[*slice_axis]
+                ));
+            }
+            let mut dims = dims.clone();
+            dims[*slice_axis] = high - low;
+            Ok(Access { dims, axis: *axis })
+        }
+        (
+            "access-concatenate",
+            [Access {
+                dims: dims_a,
+                axis: axis_a,
+            }, Access {
+                dims: dims_b,
+                axis: axis_b,
+            }, Usize(concatenate_axis)],
+        ) => {
+            check_axis("concatenate axis", *concatenate_axis, dims_a.len())?;
+            let mismatched = dims_a.len() != dims_b.len()
+                || axis_a != axis_b
+                || (0..dims_a.len()).any(|i| i != *concatenate_axis && dims_a[i] != dims_b[i]);
+            if mismatched {
+                return Err(format!(
+                    "access patterns of shapes {} and {} can't be concatenated along axis {}",
+                    format_access_shape(dims_a, *axis_a),
+                    format_access_shape(dims_b, *axis_b),
+                    concatenate_axis
+                ));
+            }
+            let mut dims = dims_a.clone();
+            dims[*concatenate_axis] += dims_b[*concatenate_axis];
+            Ok(Access {
+                dims,
+                axis: *axis_a,
+            })
+        }
+        (
+            "access-pair",
+            [Access {
+                dims: dims_a,
+                axis: axis_a,
+            }, Access {
+                dims: dims_b,
+                axis: axis_b,
+            }],
+        ) => {
+            if dims_a != dims_b || axis_a != axis_b {
+                return Err(format!(
+                    "access patterns of shapes {} and {} can't be paired",
+                    format_access_shape(dims_a, *axis_a),
+                    format_access_shape(dims_b, *axis_b)
+                ));
+            }
+            Ok(Access {
+                dims: dims_a[..*axis_a]
+                    .iter()
+                    .chain(&[2])
+                    .chain(&dims_a[*axis_a..])
+                    .copied()
+                    .collect(),
+                axis: *axis_a,
+            })
+        }
+        (operator, _) if CHECKED_OPERATORS.contains(&operator) => {
+            Err(format!("wrong kinds of arguments to \"{}\"", operator))
+        }
+        _ => Ok(Unknown),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::parse;
+
+    fn check_source(source: &str) -> Result<NodeShape, EvaluationError> {
+        let mut shapes = HashMap::new();
+        shapes.insert("t".to_string(), vec![2, 3]);
+        shapes.insert("activations".to_string(), vec![1, 3, 32, 32]);
+        shapes.insert("weights".to_string(), vec![8, 3, 3, 3]);
+        Ok(check(&parse(source).unwrap(), &shapes)?.pop().unwrap())
+    }
+
+    fn error_node(source: &str) -> Option<usize> {
+        match check_source(source) {
+            Err(EvaluationError::Shape { node, .. }) => node,
+            other => panic!("expected a shape error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conv() {
+        assert_eq!(
+            check_source(
+                "(access-transpose
+                  (compute dot-product
+                   (access-cartesian-product
+                    (access (access-tensor weights) 1)
+                    (access
+                     (access-squeeze
+                      (access-squeeze
+                       (access-windows
+                        (access
+                         (access-pad
+                          (access-pad (access-tensor activations) zero-padding 2 1 1)
+                          zero-padding 3 1 1)
+                         4)
+                        (shape 1 3 3 3)
+                        (shape 1 1 1 1))
+                       4)
+                      1)
+                     3)))
+                  (list 1 0 2 3))"
+            ),
+            Ok(NodeShape::Access {
+                dims: vec![1, 8, 32, 32],
+                axis: 4
+            })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        // Node 3 is the outer `access`.
+        assert_eq!(error_node("(access (access-tensor t) 3)"), Some(3));
+        assert_eq!(
+            error_node("(access-transpose (access-tensor t) (list 0 0))"),
+            Some(5)
+        );
+        assert_eq!(
+            error_node(
+                "(access-cartesian-product (access (access-tensor t) 1) (access (access-tensor t) 0))"
+            ),
+            Some(8)
+        );
+        assert_eq!(
+            error_node("(access-pad (access-tensor t) zero-padding 2 1 1)"),
+            Some(6)
+        );
+        assert_eq!(error_node("(access-squeeze (access-tensor t) 0)"), Some(3));
+        assert_eq!(
+            error_node("(access-windows (access (access-tensor t) 2) (shape 3 3) (shape 1 1))"),
+            Some(10)
+        );
+        assert_eq!(
+            error_node("(access-windows (access (access-tensor t) 2) (shape 2) (shape 1))"),
+            Some(8)
+        );
+        assert_eq!(
+            error_node("(compute dot-product (access (access-tensor t) 2))"),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn unknown_operators_are_not_checked() {
+        assert_eq!(
+            check_source("(access (access-flatten (access (access-tensor t) 1)) 5)"),
+            Ok(NodeShape::Unknown)
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Shape inference for Glenside programs, using the e-graph analysis which
+//! Glenside itself uses for rewriting. Only the shapes of the tensors in the
+//! environment are needed; no tensor data is touched.
+//!
+//! Programs only reach the analysis once they've passed the [`shape_check`];
+//! see there for why.
+
+use crate::evaluation::{check_bound, panic_message, parse, EvaluationError};
+use crate::result_format::format_dims;
+use crate::shape_check::{self, NodeShape};
+use egg::{EGraph, Id, Language as _, RecExpr};
+use glenside::language::{Language, MyAnalysis, MyAnalysisData};
+use ndarray::Dimension;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Infers the analysis data (most importantly, the shape) of every node of
+/// `expr`, indexed by node id. `shapes` maps the names in the environment to
+/// the shapes of their tensors.
+///
+/// Programs using operators which the shape check doesn't cover are rejected,
+/// as there's no way to know that the analysis won't panic on them.
+pub fn infer_shapes(
+    expr: &RecExpr<Language>,
+    shapes: &HashMap<String, Vec<usize>>,
+) -> Result<Vec<MyAnalysisData>, EvaluationError> {
+    let data = infer_known_shapes(expr, shapes)?;
+    match data.iter().position(Option::is_none) {
+        Some(node) => Err(shape_check::unsupported_operator(expr, node)),
+        None => Ok(data.into_iter().map(Option::unwrap).collect()),
+    }
+}
+
+/// Like [`infer_shapes`], but rather than rejecting programs using operators
+/// which the shape check doesn't cover, leaves out the data of the nodes
+/// which depend on them.
+pub fn infer_known_shapes(
+    expr: &RecExpr<Language>,
+    shapes: &HashMap<String, Vec<usize>>,
+) -> Result<Vec<Option<MyAnalysisData>>, EvaluationError> {
+    check_bound(expr, |name| shapes.contains_key(name))?;
+    let checked = shape_check::check(expr, shapes)?;
+
+    let mut egraph = EGraph::<Language, MyAnalysis>::new(MyAnalysis {
+        name_to_shape: shapes.clone(),
+        name_to_dtype: HashMap::default(),
+    });
+    let mut ids: Vec<Option<Id>> = Vec::with_capacity(expr.as_ref().len());
+    let mut data = Vec::with_capacity(expr.as_ref().len());
+
+    for (node_id, node) in expr.as_ref().iter().enumerate() {
+        if checked[node_id] == NodeShape::Unknown {
+            ids.push(None);
+            data.push(None);
+            continue;
+        }
+        // Unknown nodes are contagious, so the children of a known node are
+        // known too.
+        let node = node
+            .clone()
+            .map_children(|child| ids[usize::from(child)].unwrap());
+        // Only a backstop, for when running natively; see `shape_check`.
+        let id = catch_unwind(AssertUnwindSafe(|| egraph.add(node))).map_err(|payload| {
+            EvaluationError::Shape {
+                message: panic_message(payload),
+                node: Some(node_id),
+            }
+        })?;
+        ids.push(Some(id));
+        data.push(Some(egraph[id].data.clone()));
+    }
+
+    Ok(data)
+}
+
+/// Parses a Glenside program and infers the analysis data of its result,
+/// without interpreting it. The first shape error is found by the
+/// [`shape_check`], so this never panics, even when built for wasm.
+pub fn check_shapes(
+    source: &str,
+    shapes: &HashMap<String, Vec<usize>>,
+) -> Result<MyAnalysisData, EvaluationError> {
+    let expr = parse(source)?;
+    Ok(infer_shapes(&expr, shapes)?.pop().unwrap())
+}
+
+/// Describes the shape in `data`, e.g. `((2, 4), (2, 3))` for an access
+/// pattern. Returns `None` for nodes which don't have a shape, such as compute
+/// types.
+pub fn describe(data: &MyAnalysisData) -> Option<String> {
+    match data {
+        MyAnalysisData::AccessPattern(a) => Some(format!(
+            "(({}), ({}))",
+            format_dims(a.shape.slice()),
+            format_dims(a.item_shape.slice())
+        )),
+        MyAnalysisData::Shape(s) => Some(format!("({})", format_dims(s.shape.slice()))),
+        MyAnalysisData::Usize(u) => Some(u.to_string()),
+        MyAnalysisData::List(l) => Some(format!("({})", format_dims(l))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dense() {
+        let expr = parse(
+            "(access-cartesian-product
+              (access (access-tensor a) 1)
+              (access (access-transpose (access-tensor b) (list 1 0)) 1))",
+        )
+        .unwrap();
+        let mut shapes = HashMap::new();
+        shapes.insert("a".to_string(), vec![2, 3]);
+        shapes.insert("b".to_string(), vec![3, 4]);
+
+        let data = infer_shapes(&expr, &shapes).unwrap();
+        assert_eq!(
+            describe(data.last().unwrap()),
+            Some("((2, 4), (2, 3))".to_string())
+        );
+        assert_eq!(describe(&data[1]), Some("((), (2, 3))".to_string()));
+    }
+
+    #[test]
+    fn check() {
+        let mut shapes = HashMap::new();
+        shapes.insert("t".to_string(), vec![1, 3, 32, 32]);
+        assert_eq!(
+            describe(&check_shapes("(access (access-tensor t) 2)", &shapes).unwrap()),
+            Some("((1, 3), (32, 32))".to_string())
+        );
+        match check_shapes("(access-squeeze (access-tensor t) 1)", &shapes) {
+            Err(EvaluationError::Shape { node, .. }) => assert_eq!(node, Some(3)),
+            _ => panic!(),
+        }
+        match check_shapes("(access (access-tensor u) 2)", &shapes) {
+            Err(e) => assert_eq!(
+                e,
+                EvaluationError::UnboundVariable {
+                    name: "u".to_string(),
+                    node: 0
+                }
+            ),
+            Ok(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn shape_error() {
+        let expr = parse("(access (access-tensor a) 3)").unwrap();
+        let mut shapes = HashMap::new();
+        shapes.insert("a".to_string(), vec![2, 3]);
+
+        match infer_shapes(&expr, &shapes) {
+            Err(EvaluationError::Shape { node, .. }) => assert_eq!(node, Some(3)),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn unchecked_operator() {
+        let expr = parse("(access-flatten (access (access-tensor a) 1))").unwrap();
+        let mut shapes = HashMap::new();
+        shapes.insert("a".to_string(), vec![2, 3]);
+
+        assert_eq!(
+            infer_shapes(&expr, &shapes).err(),
+            Some(EvaluationError::UnsupportedOperator {
+                operator: "access-flatten".to_string(),
+                node: 4
+            })
+        );
+
+        let data = infer_known_shapes(&expr, &shapes).unwrap();
+        assert_eq!(
+            describe(data[3].as_ref().unwrap()),
+            Some("((2), (3))".to_string())
+        );
+        assert!(data[4].is_none());
+    }
+}
@@ -0,0 +1,195 @@
+//! Mapping from the nodes of a parsed Glenside program back to the text they
+//! came from.
+//!
+//! egg parses an s-expression into a [`RecExpr`](egg::RecExpr) by adding each
+//! node's children before the node itself, left to right. That is, node ids
+//! are assigned in post-order. We walk the source text in the same order, so
+//! the `i`th span returned by [`node_spans`] belongs to node `i`.
+
+/// A half-open range of character offsets into the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The text of `source` covered by this span.
+    pub fn text(&self, source: &str) -> String {
+        source
+            .chars()
+            .skip(self.start)
+            .take(self.end - self.start)
+            .collect()
+    }
+}
+
+enum Token {
+    Open(usize),
+    Close(usize),
+    Atom(Span),
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut atom_start = None;
+
+    for (i, c) in source.chars().enumerate() {
+        if c == '(' || c == ')' || c.is_whitespace() {
+            if let Some(start) = atom_start.take() {
+                tokens.push(Token::Atom(Span { start, end: i }));
+            }
+        }
+        match c {
+            '(' => tokens.push(Token::Open(i)),
+            ')' => tokens.push(Token::Close(i)),
+            c if c.is_whitespace() => (),
+            _ => {
+                if atom_start.is_none() {
+                    atom_start = Some(i);
+                }
+            }
+        }
+    }
+    if let Some(start) = atom_start {
+        tokens.push(Token::Atom(Span {
+            start,
+            end: source.chars().count(),
+        }));
+    }
+
+    tokens
+}
+
+/// Pushes the spans of the expression starting at `tokens[*pos]` onto `spans`
+/// in post-order. Returns `None` if the tokens don't form an expression.
+fn parse_spans(tokens: &[Token], pos: &mut usize, spans: &mut Vec<Span>) -> Option<()> {
+    match tokens.get(*pos)? {
+        Token::Atom(span) => {
+            *pos += 1;
+            spans.push(*span);
+            Some(())
+        }
+        Token::Open(start) => {
+            // Skip the operator, which doesn't get its own node.
+            *pos += 1;
+            match tokens.get(*pos)? {
+                Token::Atom(_) => *pos += 1,
+                _ => return None,
+            }
+            loop {
+                if let Token::Close(end) = tokens.get(*pos)? {
+                    *pos += 1;
+                    spans.push(Span {
+                        start: *start,
+                        end: end + 1,
+                    });
+                    return Some(());
+                }
+                parse_spans(tokens, pos, spans)?;
+            }
+        }
+        Token::Close(_) => None,
+    }
+}
+
+/// Computes the span of each node of the program in `source`, indexed by
+/// node id. Returns `None` if `source` isn't a single well-formed
+/// s-expression.
+pub fn node_spans(source: &str) -> Option<Vec<Span>> {
+    let tokens = tokenize(source);
+    let mut pos = 0;
+    let mut spans = Vec::new();
+    parse_spans(&tokens, &mut pos, &mut spans)?;
+    if pos != tokens.len() {
+        return None;
+    }
+    Some(spans)
+}
+
+/// Finds the innermost node whose span contains character `offset`, given the
+/// spans of all nodes as returned by [`node_spans`].
+pub fn innermost_node(spans: &[Span], offset: usize) -> Option<usize> {
+    spans
+        .iter()
+        .enumerate()
+        .filter(|(_, span)| span.start <= offset && offset < span.end)
+        .min_by_key(|(_, span)| span.end - span.start)
+        .map(|(id, _)| id)
+}
+
+/// Finds the span of the atom (an operator, symbol or literal) containing
+/// character `offset`, or ending just before it.
+pub fn atom_at(source: &str, offset: usize) -> Option<Span> {
+    tokenize(source).into_iter().find_map(|token| match token {
+        Token::Atom(span) if span.start <= offset && offset <= span.end => Some(span),
+        _ => None,
+    })
+}
+
+/// Converts a character offset into a 1-based (line, column) pair, as used by
+/// Monaco.
+pub fn line_and_column(source: &str, offset: usize) -> (u32, u32) {
+    let mut line = 1;
+    let mut column = 1;
+    for c in source.chars().take(offset) {
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_are_post_order() {
+        let source = "(access (access-tensor t) 1)";
+        assert_eq!(
+            node_spans(source).unwrap(),
+            vec![
+                Span { start: 23, end: 24 },
+                Span { start: 8, end: 25 },
+                Span { start: 26, end: 27 },
+                Span { start: 0, end: 28 },
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(node_spans("(access-tensor t"), None);
+        assert_eq!(node_spans("t t"), None);
+        assert_eq!(node_spans("()"), None);
+    }
+
+    #[test]
+    fn innermost() {
+        let spans = node_spans("(access (access-tensor t) 1)").unwrap();
+        assert_eq!(innermost_node(&spans, 23), Some(0));
+        assert_eq!(innermost_node(&spans, 10), Some(1));
+        assert_eq!(innermost_node(&spans, 2), Some(3));
+        assert_eq!(innermost_node(&spans, 28), None);
+    }
+
+    #[test]
+    fn atoms() {
+        let source = "(access-tensor t)";
+        assert_eq!(atom_at(source, 3), Some(Span { start: 1, end: 14 }));
+        assert_eq!(atom_at(source, 16), Some(Span { start: 15, end: 16 }));
+        assert_eq!(atom_at(source, 0), None);
+    }
+
+    #[test]
+    fn lines_and_columns() {
+        let source = "(a\n (b c))";
+        assert_eq!(line_and_column(source, 0), (1, 1));
+        assert_eq!(line_and_column(source, 2), (1, 3));
+        assert_eq!(line_and_column(source, 4), (2, 2));
+    }
+}
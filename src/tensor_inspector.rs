@@ -0,0 +1,287 @@
+//! A table showing the values of a tensor, one 2D slice at a time. The user
+//! picks which two axes run along the rows and columns of the table, fixes the
+//! index along each of the other axes with a slider, and pages through slices
+//! too big to show at once.
+
+use crate::result_format::format_dims;
+use ndarray::{ArrayD, IxDyn};
+use std::rc::Rc;
+use yew::{html, ChangeData, Component, ComponentLink, Html, InputData, Properties, ShouldRender};
+
+/// The most rows and columns shown on one page.
+const PAGE_SIZE: usize = 16;
+
+/// The axes which run along the rows and columns of the table by default, for
+/// a tensor with `ndim` dimensions. Vectors are shown as a single row, and
+/// scalars as a single cell.
+pub fn default_axes(ndim: usize) -> (Option<usize>, Option<usize>) {
+    match ndim {
+        0 => (None, None),
+        1 => (None, Some(0)),
+        n => (Some(n - 2), Some(n - 1)),
+    }
+}
+
+/// The index of the element shown in cell (`row`, `column`) of the table.
+/// `fixed` holds the index along every axis; its entries for the row and
+/// column axes are replaced.
+pub fn element_index(
+    fixed: &[usize],
+    row_axis: Option<usize>,
+    column_axis: Option<usize>,
+    row: usize,
+    column: usize,
+) -> Vec<usize> {
+    let mut index = fixed.to_vec();
+    if let Some(axis) = row_axis {
+        index[axis] = row;
+    }
+    if let Some(axis) = column_axis {
+        index[axis] = column;
+    }
+    index
+}
+
+#[derive(Properties, Clone)]
+pub struct TensorInspectorProps {
+    pub tensor: Rc<ArrayD<f64>>,
+}
+
+pub struct TensorInspector {
+    props: TensorInspectorProps,
+    link: ComponentLink<Self>,
+    row_axis: Option<usize>,
+    column_axis: Option<usize>,
+    /// The index of the slice shown, along every axis. The entries for
+    /// [`TensorInspector::row_axis`] and [`TensorInspector::column_axis`] are
+    /// ignored.
+    fixed: Vec<usize>,
+    /// The first row and column shown on the current page.
+    first_row: usize,
+    first_column: usize,
+}
+
+pub enum TensorInspectorMessage {
+    ChooseRowAxis(usize),
+    ChooseColumnAxis(usize),
+    /// Fix the index along the given axis.
+    FixIndex(usize, usize),
+    ShowRowsFrom(usize),
+    ShowColumnsFrom(usize),
+}
+
+impl TensorInspector {
+    /// Goes back to the first page of the default slice.
+    fn reset(&mut self) {
+        let (row_axis, column_axis) = default_axes(self.props.tensor.ndim());
+        self.row_axis = row_axis;
+        self.column_axis = column_axis;
+        self.fixed = vec![0; self.props.tensor.ndim()];
+        self.first_row = 0;
+        self.first_column = 0;
+    }
+
+    fn len(&self, axis: Option<usize>) -> usize {
+        axis.map(|axis| self.props.tensor.shape()[axis])
+            .unwrap_or(1)
+    }
+
+    /// A dropdown choosing one of the tensor's axes.
+    fn view_axis_chooser(
+        &self,
+        label: &str,
+        selected: Option<usize>,
+        message: fn(usize) -> TensorInspectorMessage,
+    ) -> Html {
+        html! {
+            <label>
+                {label}
+                <select onchange=self.link.callback(move |e: ChangeData| match e {
+                    ChangeData::Select(s) => message(s.selected_index() as usize),
+                    _ => unreachable!(),
+                })>
+                    { for (0..self.props.tensor.ndim()).map(|axis| html! {
+                        <option selected={selected == Some(axis)}>{axis}</option>
+                    }) }
+                </select>
+            </label>
+        }
+    }
+
+    /// A slider fixing the index along `axis`.
+    fn view_slider(&self, axis: usize) -> Html {
+        let len = self.props.tensor.shape()[axis];
+        html! {
+            <div>
+                <label>
+                    {format!("axis {}: ", axis)}
+                    <input type={"range"} min={"0"} max={len.saturating_sub(1).to_string()}
+                        value={self.fixed[axis].to_string()}
+                        oninput=self.link.callback(move |e: InputData| {
+                            TensorInspectorMessage::FixIndex(axis, e.value.parse().unwrap_or(0))
+                        }) />
+                    {format!(" {} of {}", self.fixed[axis], len)}
+                </label>
+            </div>
+        }
+    }
+
+    /// Buttons moving between pages of `len` rows or columns, the first shown
+    /// being `first`.
+    fn view_pager(
+        &self,
+        label: &str,
+        first: usize,
+        len: usize,
+        message: fn(usize) -> TensorInspectorMessage,
+    ) -> Html {
+        if len <= PAGE_SIZE {
+            return html! {};
+        }
+        html! {
+            <div>
+                <input type={"button"} value={"previous"} disabled={first == 0}
+                    onclick=self.link.callback(move |_| message(first.saturating_sub(PAGE_SIZE))) />
+                <input type={"button"} value={"next"} disabled={first + PAGE_SIZE >= len}
+                    onclick=self.link.callback(move |_| message(first + PAGE_SIZE)) />
+                {format!(
+                    " {} {}-{} of {}",
+                    label,
+                    first,
+                    (first + PAGE_SIZE).min(len) - 1,
+                    len
+                )}
+            </div>
+        }
+    }
+}
+
+impl Component for TensorInspector {
+    type Message = TensorInspectorMessage;
+    type Properties = TensorInspectorProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut inspector = Self {
+            props,
+            link,
+            row_axis: None,
+            column_axis: None,
+            fixed: vec![],
+            first_row: 0,
+            first_column: 0,
+        };
+        inspector.reset();
+        inspector
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            TensorInspectorMessage::ChooseRowAxis(axis) => {
+                if self.column_axis == Some(axis) {
+                    self.column_axis = self.row_axis;
+                }
+                self.row_axis = Some(axis);
+                self.first_row = 0;
+                self.first_column = 0;
+            }
+            TensorInspectorMessage::ChooseColumnAxis(axis) => {
+                if self.row_axis == Some(axis) {
+                    self.row_axis = self.column_axis;
+                }
+                self.column_axis = Some(axis);
+                self.first_row = 0;
+                self.first_column = 0;
+            }
+            TensorInspectorMessage::FixIndex(axis, index) => self.fixed[axis] = index,
+            TensorInspectorMessage::ShowRowsFrom(row) => self.first_row = row,
+            TensorInspectorMessage::ShowColumnsFrom(column) => self.first_column = column,
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let same_shape = props.tensor.shape() == self.props.tensor.shape();
+        self.props = props;
+        // Keep looking at the same slice, if it still exists.
+        if !same_shape {
+            self.reset();
+        }
+        true
+    }
+
+    fn view(&self) -> Html {
+        let tensor = &self.props.tensor;
+        if tensor.is_empty() {
+            return html! { <p>{"(empty tensor)"}</p> };
+        }
+        if tensor.ndim() == 0 {
+            return html! {
+                <p>{format!("{:.4}", tensor[IxDyn(&[])])}</p>
+            };
+        }
+
+        let (rows, columns) = (self.len(self.row_axis), self.len(self.column_axis));
+        let shown_rows = self.first_row..(self.first_row + PAGE_SIZE).min(rows);
+        let shown_columns = self.first_column..(self.first_column + PAGE_SIZE).min(columns);
+        let cell_style = "border: 1px solid #888; padding: 2px; text-align: right";
+
+        html! {
+            <div>
+                {
+                    if tensor.ndim() >= 2 {
+                        html! {
+                            <div>
+                                {self.view_axis_chooser("rows: axis ", self.row_axis, TensorInspectorMessage::ChooseRowAxis)}
+                                {" "}
+                                {self.view_axis_chooser("columns: axis ", self.column_axis, TensorInspectorMessage::ChooseColumnAxis)}
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                { for (0..tensor.ndim())
+                    .filter(|&axis| Some(axis) != self.row_axis && Some(axis) != self.column_axis)
+                    .map(|axis| self.view_slider(axis)) }
+                {self.view_pager("rows", self.first_row, rows, TensorInspectorMessage::ShowRowsFrom)}
+                {self.view_pager("columns", self.first_column, columns, TensorInspectorMessage::ShowColumnsFrom)}
+                <table style={"border-collapse: collapse; font-family: monospace"}>
+                    <tr>
+                        <th></th>
+                        { for shown_columns.clone().map(|column| html! { <th>{column}</th> }) }
+                    </tr>
+                    { for shown_rows.map(|row| html! {
+                        <tr>
+                            <th>{row}</th>
+                            { for shown_columns.clone().map(|column| {
+                                let index = element_index(&self.fixed, self.row_axis, self.column_axis, row, column);
+                                html! {
+                                    <td style={cell_style} title={format!("({})", format_dims(&index))}>
+                                        {format!("{:.2}", tensor[IxDyn(&index)])}
+                                    </td>
+                                }
+                            }) }
+                        </tr>
+                    }) }
+                </table>
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices() {
+        assert_eq!(default_axes(1), (None, Some(0)));
+        assert_eq!(default_axes(4), (Some(2), Some(3)));
+
+        assert_eq!(element_index(&[0], None, Some(0), 0, 3), vec![3]);
+        assert_eq!(
+            element_index(&[1, 2, 0, 0], Some(3), Some(0), 4, 5),
+            vec![5, 2, 0, 4]
+        );
+    }
+}
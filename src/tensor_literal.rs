@@ -0,0 +1,216 @@
+//! Parsing of tensors written out as nested lists, e.g. `[[1, 2, 3], [4, 5,
+//! 6]]`. Elements can be separated by commas or, as numpy prints them, by
+//! whitespace; a bare number is a scalar.
+
+use crate::result_format::format_dims;
+use ndarray::{ArrayD, ArrayViewD, Axis, IxDyn};
+
+struct Parser<'a> {
+    source: &'a str,
+    /// The byte offset of the next character to parse.
+    position: usize,
+    /// The elements parsed so far, in row-major order.
+    values: Vec<f64>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.source[self.position..].chars().next()
+    }
+
+    /// Parses a number or a list, returning its shape.
+    fn parse_element(&mut self) -> Result<Vec<usize>, String> {
+        match self.peek() {
+            Some('[') => {
+                self.position += 1;
+                self.parse_list()
+            }
+            Some(c) if c == ']' || c == ',' => Err(format!(
+                "expected a number or '[' at character {}, found '{}'",
+                self.position, c
+            )),
+            Some(_) => {
+                let rest = &self.source[self.position..];
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || c == '[' || c == ']' || c == ',')
+                    .unwrap_or(rest.len());
+                let number = &rest[..len];
+                let value = number
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number '{}'", number))?;
+                self.values.push(value);
+                self.position += len;
+                Ok(vec![])
+            }
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    /// Parses the rest of a list, whose opening '[' has been consumed,
+    /// returning its shape.
+    fn parse_list(&mut self) -> Result<Vec<usize>, String> {
+        let mut element_shape = None;
+        let mut len = 0;
+        loop {
+            match self.peek() {
+                Some(']') => {
+                    self.position += 1;
+                    break;
+                }
+                Some(',') if len > 0 => self.position += 1,
+                _ => (),
+            }
+            if self.peek() == Some(']') {
+                continue;
+            }
+
+            let shape = self.parse_element()?;
+            match &element_shape {
+                None => element_shape = Some(shape),
+                Some(expected) if *expected != shape => {
+                    return Err(format!(
+                        "element {} of a list has shape ({}), but element 0 has shape ({})",
+                        len,
+                        format_dims(&shape),
+                        format_dims(expected)
+                    ))
+                }
+                Some(_) => (),
+            }
+            len += 1;
+        }
+
+        let mut shape = vec![len];
+        shape.extend(element_shape.unwrap_or_default());
+        Ok(shape)
+    }
+}
+
+/// Parses a tensor written as a nested list.
+pub fn parse_tensor_literal(source: &str) -> Result<ArrayD<f64>, String> {
+    let mut parser = Parser {
+        source,
+        position: 0,
+        values: vec![],
+    };
+    let shape = parser.parse_element()?;
+    if parser.peek().is_some() {
+        return Err(format!(
+            "unexpected text after the end of the tensor, at character {}",
+            parser.position
+        ));
+    }
+    Ok(ArrayD::from_shape_vec(IxDyn(&shape), parser.values).unwrap())
+}
+
+/// Parses a tensor written as a nested list, checking that it has the given
+/// shape.
+pub fn parse_tensor_literal_with_shape(
+    source: &str,
+    shape: &[usize],
+) -> Result<ArrayD<f64>, String> {
+    let tensor = parse_tensor_literal(source)?;
+    if tensor.shape() != shape {
+        return Err(format!(
+            "values have shape ({}), but the shape given is ({})",
+            format_dims(tensor.shape()),
+            format_dims(shape)
+        ));
+    }
+    Ok(tensor)
+}
+
+/// Writes a tensor as a nested list, which [`parse_tensor_literal`] parses
+/// back into the same tensor. Tensors with no elements and more than one
+/// dimension can't be written this way, as their shape would be lost.
+pub fn format_tensor_literal(tensor: ArrayViewD<f64>) -> Option<String> {
+    if tensor.is_empty() && tensor.ndim() > 1 {
+        return None;
+    }
+    if tensor.ndim() == 0 {
+        return Some(tensor.iter().next().unwrap().to_string());
+    }
+    Some(format!(
+        "[{}]",
+        tensor
+            .axis_iter(Axis(0))
+            .map(|element| format_tensor_literal(element).unwrap())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_tensor_literal("[[1,2,3],[4,5,6]]").unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 3]), vec![1., 2., 3., 4., 5., 6.]).unwrap()
+        );
+        // As printed by numpy.
+        assert_eq!(
+            parse_tensor_literal("[[1. 2.]\n [3. 4.]]").unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 2]), vec![1., 2., 3., 4.]).unwrap()
+        );
+        assert_eq!(
+            parse_tensor_literal(" -2.5 ").unwrap(),
+            ArrayD::from_elem(IxDyn(&[]), -2.5)
+        );
+        assert_eq!(parse_tensor_literal("[]").unwrap().shape(), &[0]);
+        assert_eq!(parse_tensor_literal("[1, 2,]").unwrap().shape(), &[2]);
+    }
+
+    #[test]
+    fn format() {
+        let tensor =
+            ArrayD::from_shape_vec(IxDyn(&[2, 3]), vec![1., -2.5, 0.1, 1e-7, 4., 5.]).unwrap();
+        let literal = format_tensor_literal(tensor.view()).unwrap();
+        assert_eq!(literal, "[[1, -2.5, 0.1], [0.0000001, 4, 5]]");
+        assert_eq!(parse_tensor_literal(&literal).unwrap(), tensor);
+        assert_eq!(
+            format_tensor_literal(ArrayD::from_elem(IxDyn(&[]), 3.).view()).unwrap(),
+            "3"
+        );
+        assert_eq!(
+            format_tensor_literal(ArrayD::zeros(IxDyn(&[0])).view()).unwrap(),
+            "[]"
+        );
+        assert_eq!(
+            format_tensor_literal(ArrayD::zeros(IxDyn(&[0, 3])).view()),
+            None
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_tensor_literal("[[1, 2], [3]]").unwrap_err(),
+            "element 1 of a list has shape (1), but element 0 has shape (2)"
+        );
+        assert_eq!(
+            parse_tensor_literal("[1, x]").unwrap_err(),
+            "invalid number 'x'"
+        );
+        assert_eq!(
+            parse_tensor_literal("[1, 2").unwrap_err(),
+            "unexpected end of input"
+        );
+        assert_eq!(
+            parse_tensor_literal("[1] 2").unwrap_err(),
+            "unexpected text after the end of the tensor, at character 4"
+        );
+        assert_eq!(
+            parse_tensor_literal_with_shape("[1, 2]", &[3]).unwrap_err(),
+            "values have shape (2), but the shape given is (3)"
+        );
+    }
+}
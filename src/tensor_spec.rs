@@ -0,0 +1,168 @@
+//! Specifications of the tensors in the environment: everything the user has
+//! entered into an environment input, from which the tensor's value can be
+//! rebuilt. Unlike the values themselves, these are small enough to save.
+
+use crate::import;
+use crate::tensor_literal;
+use crate::value_generation::{self, ValueGenerationStrategy};
+use ndarray::ArrayD;
+use serde::{Deserialize, Serialize};
+
+/// The most elements a tensor may have. Specs come from permalinks and local
+/// storage as well as from the user, and a huge shape would otherwise run the
+/// browser out of memory.
+pub const MAX_ELEMENTS: usize = 1 << 22;
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TensorSpec {
+    pub name: String,
+    /// The shape as typed by the user, e.g. `(3,32,32)`. See
+    /// [`TensorSpec::shape`].
+    pub shape_string: String,
+    pub strategy: Option<ValueGenerationStrategy>,
+    /// The values typed in by the user, used by
+    /// [`ValueGenerationStrategy::Explicit`] and [`ValueGenerationStrategy::Csv`].
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub values_string: String,
+}
+
+impl TensorSpec {
+    /// The spec of a tensor with the given shape and random values.
+    pub fn random(name: &str, shape: &[usize]) -> Self {
+        Self {
+            name: name.to_string(),
+            shape_string: format_shape(shape),
+            strategy: Some(ValueGenerationStrategy::Random),
+            values_string: String::default(),
+        }
+    }
+
+    /// The spec of `value`, written out as explicit values. See
+    /// [`tensor_literal::format_tensor_literal`] for the tensors which can't
+    /// be.
+    pub fn explicit(name: &str, value: &ArrayD<f64>) -> Option<Self> {
+        Some(Self {
+            name: name.to_string(),
+            shape_string: format_shape(value.shape()),
+            strategy: Some(ValueGenerationStrategy::Explicit),
+            values_string: tensor_literal::format_tensor_literal(value.view())?,
+        })
+    }
+
+    /// Parses the shape string, e.g. `(3,32,32)`. Shapes of tensors with more
+    /// than [`MAX_ELEMENTS`] elements are rejected.
+    pub fn shape(&self) -> Option<Vec<usize>> {
+        let dims = self.shape_string.strip_prefix('(')?.strip_suffix(')')?;
+        let shape = if dims.is_empty() {
+            vec![]
+        } else {
+            dims.split(',')
+                .map(|dim| dim.parse().ok())
+                .collect::<Option<Vec<usize>>>()?
+        };
+
+        // ndarray's limits ignore zero-length dimensions, so these do too.
+        let elements = shape
+            .iter()
+            .try_fold(1usize, |elements, &dim| elements.checked_mul(dim.max(1)))?;
+        if elements > MAX_ELEMENTS {
+            return None;
+        }
+        Some(shape)
+    }
+
+    /// Parses the explicit values typed by the user, checking them against
+    /// the shape. CSV is reshaped to the shape instead, if it has the right
+    /// number of values.
+    pub fn explicit_value(&self) -> Result<ArrayD<f64>, String> {
+        let shape = self.shape().ok_or_else(|| "invalid shape".to_string())?;
+        match self.strategy {
+            Some(ValueGenerationStrategy::Csv) => {
+                import::parse_csv_with_shape(&self.values_string, &shape)
+            }
+            _ => tensor_literal::parse_tensor_literal_with_shape(&self.values_string, &shape),
+        }
+    }
+
+    /// Builds the tensor, if the spec is valid. Random values are generated
+    /// from a seed derived from `seed` and the tensor's name.
+    pub fn value(&self, seed: u64) -> Option<ArrayD<f64>> {
+        let shape = self.shape()?;
+
+        match self.strategy? {
+            ValueGenerationStrategy::Explicit | ValueGenerationStrategy::Csv => {
+                self.explicit_value().ok()
+            }
+            strategy => strategy.generate(&shape, value_generation::derive_seed(seed, &self.name)),
+        }
+    }
+}
+
+/// Formats a shape the way the user types it, e.g. `(3,32,32)`.
+fn format_shape(shape: &[usize]) -> String {
+    format!(
+        "({})",
+        shape
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::IxDyn;
+
+    #[test]
+    fn value() {
+        let spec = TensorSpec::random("t", &[2, 3]);
+        assert_eq!(spec.shape_string, "(2,3)");
+        assert_eq!(
+            spec.value(1),
+            ValueGenerationStrategy::Random
+                .generate(&[2, 3], value_generation::derive_seed(1, "t"))
+        );
+
+        let spec = TensorSpec {
+            shape_string: "(2,2)".to_string(),
+            strategy: Some(ValueGenerationStrategy::Csv),
+            values_string: "1,2,3,4".to_string(),
+            ..spec
+        };
+        assert_eq!(
+            spec.value(1).unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 2]), vec![1., 2., 3., 4.]).unwrap()
+        );
+
+        let value = ArrayD::from_shape_vec(IxDyn(&[3, 1]), vec![1., 2.5, -3.]).unwrap();
+        let spec = TensorSpec::explicit("u", &value).unwrap();
+        assert_eq!(spec.shape_string, "(3,1)");
+        assert_eq!(spec.value(1).unwrap(), value);
+
+        assert_eq!(TensorSpec::default().value(0), None);
+        let shape = |shape_string: &str| {
+            TensorSpec {
+                shape_string: shape_string.to_string(),
+                ..TensorSpec::default()
+            }
+            .shape()
+        };
+        assert_eq!(shape("()"), Some(vec![]));
+        assert_eq!(shape("(0,3)"), Some(vec![0, 3]));
+        assert_eq!(shape("(é)"), None);
+        assert_eq!(shape("2,3"), None);
+        assert_eq!(shape("(100000,100000)"), None);
+        assert_eq!(shape("(0,100000000000)"), None);
+        assert_eq!(
+            TensorSpec {
+                shape_string: "(2,x)".to_string(),
+                ..TensorSpec::default()
+            }
+            .explicit_value()
+            .unwrap_err(),
+            "invalid shape"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! The ways in which the values of a tensor in the environment can be
+//! generated, given its shape.
+
+use ndarray::{ArrayD, Dimension, IxDyn};
+use rand::{
+    distributions::{Distribution, Normal, Uniform},
+    SeedableRng,
+};
+use rand_hc::Hc128Rng;
+use serde::{Deserialize, Serialize};
+
+/// Derives the seed for the tensor called `name` from the global seed, so
+/// that different tensors get different values. Uses FNV-1a, which, unlike the
+/// standard library's hashers, is guaranteed not to change, so a seed gives the
+/// same tensors everywhere. For the same reason, values are drawn from a named
+/// generator, HC-128, rather than from rand's `StdRng`, which may change.
+pub fn derive_seed(seed: u64, name: &str) -> u64 {
+    seed.to_le_bytes()
+        .iter()
+        .chain(name.as_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ValueGenerationStrategy {
+    /// Values drawn uniformly from [-2, 2), seeded by the seed passed to
+    /// [`ValueGenerationStrategy::generate`].
+    Random,
+    Zeros,
+    Ones,
+    /// Values typed in by the user, as a nested list. These aren't generated;
+    /// see [`crate::tensor_literal`].
+    Explicit,
+    /// Values pasted in by the user as CSV, e.g. from a spreadsheet, and
+    /// reshaped to the tensor's shape. See [`crate::import`].
+    Csv,
+    /// Each element is its index into the flattened tensor, which makes it
+    /// easy to see where data moves.
+    Arange,
+    /// Ones where all of an element's indices are equal, and zeros elsewhere.
+    Identity,
+    Constant(f64),
+    Normal {
+        mean: f64,
+        std_dev: f64,
+        seed: u64,
+    },
+    Uniform {
+        low: f64,
+        high: f64,
+        seed: u64,
+    },
+}
+
+impl ValueGenerationStrategy {
+    /// Generates a tensor of the given shape. `seed` is used by
+    /// [`ValueGenerationStrategy::Random`]; the other random strategies carry
+    /// their own seeds. Returns `None` if the strategy's parameters are
+    /// invalid, or for [`ValueGenerationStrategy::Explicit`] and
+    /// [`ValueGenerationStrategy::Csv`].
+    pub fn generate(&self, shape: &[usize], seed: u64) -> Option<ArrayD<f64>> {
+        let shape = IxDyn(shape);
+        match *self {
+            ValueGenerationStrategy::Zeros => Some(ArrayD::zeros(shape)),
+            ValueGenerationStrategy::Ones => Some(ArrayD::ones(shape)),
+            ValueGenerationStrategy::Random => ValueGenerationStrategy::Uniform {
+                low: -2.0,
+                high: 2.0,
+                seed,
+            }
+            .generate(shape.slice(), seed),
+            ValueGenerationStrategy::Explicit | ValueGenerationStrategy::Csv => None,
+            ValueGenerationStrategy::Arange => {
+                let len = shape.size();
+                Some(ArrayD::from_shape_vec(shape, (0..len).map(|i| i as f64).collect()).unwrap())
+            }
+            ValueGenerationStrategy::Identity => Some(ArrayD::from_shape_fn(shape, |index| {
+                let index = index.slice();
+                if index.iter().all(|&i| i == index[0]) {
+                    1.0
+                } else {
+                    0.0
+                }
+            })),
+            ValueGenerationStrategy::Constant(c) => Some(ArrayD::from_elem(shape, c)),
+            ValueGenerationStrategy::Normal {
+                mean,
+                std_dev,
+                seed,
+            } => {
+                if std_dev.is_nan() || std_dev < 0.0 {
+                    return None;
+                }
+                let distribution = Normal::new(mean, std_dev);
+                let mut rng = Hc128Rng::seed_from_u64(seed);
+                Some(ArrayD::from_shape_fn(shape, |_| {
+                    distribution.sample(&mut rng)
+                }))
+            }
+            ValueGenerationStrategy::Uniform { low, high, seed } => {
+                if !(high - low).is_finite() || low >= high {
+                    return None;
+                }
+                let distribution = Uniform::new(low, high);
+                let mut rng = Hc128Rng::seed_from_u64(seed);
+                Some(ArrayD::from_shape_fn(shape, |_| {
+                    distribution.sample(&mut rng)
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arange_and_identity() {
+        assert_eq!(
+            ValueGenerationStrategy::Arange
+                .generate(&[2, 2], 0)
+                .unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 2]), vec![0., 1., 2., 3.]).unwrap()
+        );
+        assert_eq!(
+            ValueGenerationStrategy::Identity
+                .generate(&[2, 3], 0)
+                .unwrap(),
+            ArrayD::from_shape_vec(IxDyn(&[2, 3]), vec![1., 0., 0., 0., 1., 0.]).unwrap()
+        );
+    }
+
+    #[test]
+    fn seeded() {
+        assert_eq!(
+            ValueGenerationStrategy::Random.generate(&[4], 3),
+            ValueGenerationStrategy::Random.generate(&[4], 3)
+        );
+        assert_ne!(
+            ValueGenerationStrategy::Random.generate(&[4], 3),
+            ValueGenerationStrategy::Random.generate(&[4], 4)
+        );
+        assert_ne!(derive_seed(0, "a"), derive_seed(0, "b"));
+        assert_ne!(derive_seed(0, "a"), derive_seed(1, "a"));
+
+        let normal = ValueGenerationStrategy::Normal {
+            mean: 0.0,
+            std_dev: 1.0,
+            seed: 7,
+        };
+        assert_eq!(normal.generate(&[4], 0), normal.generate(&[4], 0));
+
+        let uniform = |seed| ValueGenerationStrategy::Uniform {
+            low: 1.0,
+            high: 2.0,
+            seed,
+        };
+        let values = uniform(1).generate(&[8], 0).unwrap();
+        assert!(values.iter().all(|v| (1.0..2.0).contains(v)));
+        assert_eq!(Some(values.clone()), uniform(1).generate(&[8], 0));
+        assert_ne!(Some(values), uniform(2).generate(&[8], 0));
+    }
+
+    #[test]
+    fn values_are_pinned() {
+        // Permalinks hold seeds rather than values, so these must never
+        // change.
+        assert_eq!(
+            ValueGenerationStrategy::Random.generate(&[4], 0).unwrap(),
+            ArrayD::from_shape_vec(
+                IxDyn(&[4]),
+                vec![
+                    -0.21611051281172333,
+                    1.670795280587697,
+                    -0.7200190547960572,
+                    -1.9562621267918034
+                ]
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert_eq!(
+            ValueGenerationStrategy::Uniform {
+                low: 1.0,
+                high: 1.0,
+                seed: 0
+            }
+            .generate(&[1], 0),
+            None
+        );
+        assert_eq!(
+            ValueGenerationStrategy::Normal {
+                mean: 0.0,
+                std_dev: -1.0,
+                seed: 0
+            }
+            .generate(&[1], 0),
+            None
+        );
+    }
+}
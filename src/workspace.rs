@@ -0,0 +1,280 @@
+//! The state of the user's workspace: their programs, the specs of their
+//! environment, the seed and the selected example. A workspace can be encoded
+//! in a URL fragment, making a permalink to it, and is saved in the browser's
+//! local storage so that it survives reloads.
+
+use crate::tensor_spec::{TensorSpec, MAX_ELEMENTS};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use ndarray::ArrayD;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Explicit values longer than this are left out of permalinks, to keep them
+/// a reasonable length. Their inputs are restored with no values. Tensors
+/// without specs of their own are only saved if their values fit too.
+const MAX_PERMALINK_VALUES_LEN: usize = 2000;
+
+/// The key under which the workspace is saved in local storage.
+const STORAGE_KEY: &str = "glenside-web-demo-workspace";
+
+/// The most bytes a permalink may decompress to, so that a malicious link
+/// can't use up all of the browser's memory.
+const MAX_DECOMPRESSED_LEN: u64 = 1 << 20;
+
+/// The most elements the tensors of a workspace may have between them. Each
+/// tensor is limited to [`MAX_ELEMENTS`] by its spec, but a permalink could
+/// still hold a great many of them.
+const MAX_TOTAL_ELEMENTS: usize = 4 * MAX_ELEMENTS;
+
+/// A program open in one of the editor's tabs.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ProgramTab {
+    pub name: String,
+    pub program: String,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub tabs: Vec<ProgramTab>,
+    /// The index into [`Workspace::tabs`] of the tab shown in the editor.
+    #[serde(default)]
+    pub current_tab: usize,
+    /// The name of the selected example, if any. Names are used rather than
+    /// indices so that permalinks survive examples being added.
+    #[serde(default)]
+    pub example: Option<String>,
+    /// The specs of the tensors in the example's environment, as edited by
+    /// the user.
+    #[serde(default)]
+    pub example_environment: Vec<TensorSpec>,
+    /// The specs of the tensors the user has added to the environment.
+    #[serde(default)]
+    pub environment: Vec<TensorSpec>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Workspace {
+    /// Encodes the workspace as compressed, URL-safe text, to go after the `#`
+    /// of a permalink.
+    pub fn to_fragment(&self) -> String {
+        let mut workspace = self.clone();
+        for spec in workspace
+            .example_environment
+            .iter_mut()
+            .chain(workspace.environment.iter_mut())
+        {
+            if spec.values_string.len() > MAX_PERMALINK_VALUES_LEN {
+                spec.values_string.clear();
+            }
+        }
+
+        let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+        serde_json::to_writer(&mut encoder, &workspace).unwrap();
+        encoder.flush().unwrap();
+        base64::encode_config(encoder.finish().unwrap(), base64::URL_SAFE_NO_PAD)
+    }
+
+    /// The names of the inputs whose values [`Workspace::to_fragment`] leaves
+    /// out, as they're too long.
+    pub fn values_left_out_of_fragment(&self) -> Vec<String> {
+        self.example_environment
+            .iter()
+            .chain(&self.environment)
+            .filter(|spec| spec.values_string.len() > MAX_PERMALINK_VALUES_LEN)
+            .map(|spec| spec.name.clone())
+            .collect()
+    }
+
+    /// Adds explicit specs for tensors which don't have specs of their own,
+    /// such as those imported from files or bound by the notebook, so that
+    /// they're saved too. Returns the names of those which are too large to
+    /// be saved this way.
+    pub fn add_explicit_tensors<'a>(
+        &mut self,
+        tensors: impl IntoIterator<Item = (&'a str, &'a ArrayD<f64>)>,
+    ) -> Vec<String> {
+        let mut left_out = vec![];
+        for (name, value) in tensors {
+            // Every value takes at least one character, so don't bother
+            // writing out tensors which are bound to be too long.
+            let spec = if value.len() <= MAX_PERMALINK_VALUES_LEN {
+                TensorSpec::explicit(name, value)
+            } else {
+                None
+            };
+            match spec.filter(|spec| spec.values_string.len() <= MAX_PERMALINK_VALUES_LEN) {
+                Some(spec) => self.environment.push(spec),
+                None => left_out.push(name.to_string()),
+            }
+        }
+        left_out
+    }
+
+    /// Decodes a workspace encoded by [`Workspace::to_fragment`]. A leading
+    /// `#` is ignored.
+    pub fn from_fragment(fragment: &str) -> Result<Self, String> {
+        let compressed =
+            base64::decode_config(fragment.trim_start_matches('#'), base64::URL_SAFE_NO_PAD)
+                .map_err(|e| format!("invalid permalink: {}", e))?;
+        let mut json = vec![];
+        DeflateDecoder::new(&compressed[..])
+            .take(MAX_DECOMPRESSED_LEN)
+            .read_to_end(&mut json)
+            .map_err(|e| format!("invalid permalink: {}", e))?;
+        let workspace: Self =
+            serde_json::from_slice(&json).map_err(|e| format!("invalid permalink: {}", e))?;
+        workspace
+            .check_size()
+            .map_err(|e| format!("invalid permalink: {}", e))?;
+        Ok(workspace)
+    }
+
+    /// Checks that the workspace's tensors aren't too large to build, between
+    /// them.
+    fn check_size(&self) -> Result<(), String> {
+        let elements = self
+            .example_environment
+            .iter()
+            .chain(&self.environment)
+            .filter_map(|spec| spec.shape())
+            .map(|shape| shape.iter().product::<usize>())
+            .fold(0, usize::saturating_add);
+        if elements > MAX_TOTAL_ELEMENTS {
+            return Err(format!(
+                "its tensors have {} elements between them, but at most {} are allowed",
+                elements, MAX_TOTAL_ELEMENTS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Loads the workspace last saved with [`Workspace::save`], if there is
+    /// one.
+    pub fn load() -> Option<Self> {
+        let json = local_storage()?.get_item(STORAGE_KEY).ok()??;
+        serde_json::from_str(&json)
+            .map_err(|e| e.to_string())
+            .and_then(|workspace: Self| workspace.check_size().map(|_| workspace))
+            .map_err(|e| log::warn!("couldn't load the saved workspace: {}", e))
+            .ok()
+    }
+
+    /// Saves the workspace in local storage. Failures, e.g. because storage
+    /// is disabled or full, are only logged.
+    pub fn save(&self) {
+        if let Some(storage) = local_storage() {
+            if let Err(e) = storage.set_item(STORAGE_KEY, &serde_json::to_string(self).unwrap()) {
+                log::warn!("couldn't save the workspace: {:?}", e);
+            }
+        }
+    }
+
+    /// Deletes the saved workspace, if there is one.
+    pub fn clear_saved() {
+        if let Some(storage) = local_storage() {
+            storage.remove_item(STORAGE_KEY).ok();
+        }
+    }
+}
+
+/// The browser's local storage, if it's available.
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value_generation::ValueGenerationStrategy;
+    use ndarray::IxDyn;
+
+    #[test]
+    fn round_trip() {
+        let workspace = Workspace {
+            tabs: vec![
+                ProgramTab {
+                    name: "program 1".to_string(),
+                    program: "u".to_string(),
+                },
+                ProgramTab {
+                    name: "Our first access pattern".to_string(),
+                    program: "(access-tensor t)".to_string(),
+                },
+            ],
+            current_tab: 1,
+            example: Some("Our first access pattern".to_string()),
+            example_environment: vec![TensorSpec::random("t", &[2, 3])],
+            environment: vec![TensorSpec {
+                name: "u".to_string(),
+                shape_string: "(2)".to_string(),
+                strategy: Some(ValueGenerationStrategy::Explicit),
+                values_string: "[1, 2]".to_string(),
+            }],
+            seed: 3,
+        };
+        let fragment = workspace.to_fragment();
+        assert!(fragment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Workspace::from_fragment(&fragment).unwrap(), workspace);
+        assert_eq!(
+            Workspace::from_fragment(&format!("#{}", fragment)).unwrap(),
+            workspace
+        );
+        assert!(Workspace::from_fragment("not a permalink!").is_err());
+    }
+
+    #[test]
+    fn large_values_are_dropped() {
+        let mut workspace = Workspace::default();
+        workspace.environment.push(TensorSpec {
+            name: "u".to_string(),
+            values_string: "1,".repeat(MAX_PERMALINK_VALUES_LEN),
+            ..TensorSpec::default()
+        });
+        assert_eq!(
+            workspace.values_left_out_of_fragment(),
+            vec!["u".to_string()]
+        );
+        let decoded = Workspace::from_fragment(&workspace.to_fragment()).unwrap();
+        assert_eq!(decoded.environment[0].values_string, "");
+    }
+
+    #[test]
+    fn large_workspaces_are_rejected() {
+        let mut workspace = Workspace::default();
+        for i in 0..4 {
+            workspace
+                .environment
+                .push(TensorSpec::random(&i.to_string(), &[MAX_ELEMENTS]));
+        }
+        assert!(Workspace::from_fragment(&workspace.to_fragment()).is_ok());
+        workspace.environment.push(TensorSpec::random("4", &[1]));
+        assert_eq!(
+            Workspace::from_fragment(&workspace.to_fragment()).unwrap_err(),
+            format!(
+                "invalid permalink: its tensors have {} elements between them, \
+                 but at most {} are allowed",
+                MAX_TOTAL_ELEMENTS + 1,
+                MAX_TOTAL_ELEMENTS
+            )
+        );
+    }
+
+    #[test]
+    fn explicit_tensors() {
+        let small = ArrayD::from_shape_vec(IxDyn(&[2]), vec![1., 2.]).unwrap();
+        let large = ArrayD::zeros(IxDyn(&[MAX_PERMALINK_VALUES_LEN]));
+        let mut workspace = Workspace::default();
+        assert_eq!(
+            workspace.add_explicit_tensors(vec![("small", &small), ("large", &large)]),
+            vec!["large".to_string()]
+        );
+        assert_eq!(workspace.environment.len(), 1);
+        let decoded = Workspace::from_fragment(&workspace.to_fragment()).unwrap();
+        assert_eq!(decoded.environment[0].name, "small");
+        assert_eq!(decoded.environment[0].value(0).unwrap(), small);
+    }
+}